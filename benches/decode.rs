@@ -0,0 +1,43 @@
+//! Decodes The Washington Post march repeatedly and reports the throughput. Compare against the scalar code with
+//! `cargo bench --features no-simd` or `--features forbid-unsafe`, and add `--features std` for AVX2 to be detected
+//! at runtime.
+
+use std::{hint::black_box, time::{Duration, Instant}};
+
+const MARCH: &[u8] = include_bytes!("../src/tests/The Washington Post.mp3");
+const ROUNDS: usize = 20;
+
+/// Decodes the whole march, returning the length of the audio.
+fn decode(mut mp3: &[u8], pcm: &mut [f32]) -> f64 {
+    let mut decoder = nanomp3::Decoder::new();
+    let mut time = 0.;
+    while !mp3.is_empty() {
+        let (consumed, info) = decoder.decode(mp3, pcm);
+        if let Some(info) = info {
+            time += info.samples_produced as f64 / f64::from(info.sample_rate);
+        }
+        black_box(&pcm);
+        mp3 = &mp3[consumed..];
+    }
+    time
+}
+
+fn main() {
+    let mut pcm = [0f32; nanomp3::MAX_SAMPLES_PER_FRAME];
+    let audio = decode(MARCH, &mut pcm);
+
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        decode(black_box(MARCH), &mut pcm);
+        best = best.min(start.elapsed());
+    }
+
+    // The fixed-point build has no SIMD kernels either
+    let simd = if cfg!(any(feature = "no-simd", feature = "forbid-unsafe", feature = "fixed-point")) { "scalar" } else { "SIMD" };
+    println!(
+        "{simd}: {:.2} ms per decode of {audio:.1} s of audio, {:.0}x realtime",
+        best.as_secs_f64() * 1e3,
+        audio / best.as_secs_f64()
+    );
+}
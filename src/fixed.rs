@@ -0,0 +1,298 @@
+//! The number types the decode pipeline computes in with the `fixed-point` feature, standing in for `float.rs`
+//! on targets without a hardware FPU.
+//!
+//! Spectral lines and subband samples are Q27, which leaves room for the ±1 or so they stay within and for the
+//! largest coefficient of the DCT, just over 10. Sums and products saturate, so that masters too hot for that clip
+//! instead of wrapping round to the opposite sign. Products are formed in `i64`. The weights of the synthesis window
+//! are integers, so it sums into a 64-bit accumulator that keeps all 27 fractional bits until the sample is
+//! converted for output.
+#![allow(non_camel_case_types, non_snake_case)]
+
+use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::sample::sealed::Sealed;
+
+use super::minimp3::tables::G_POW43;
+
+/// The fractional bits of [`mp3d_real_t`] and [`mp3d_acc_t`].
+pub const FRAC_BITS: u32 = 27;
+
+/// `2^(-i/4)` in Q30
+static L3_LDEXP_Q2_G_EXPFRAC: [i32; 4] = [1073741824, 902905651, 759250125, 638450708];
+
+/// Spectral lines, subband samples and the coefficients they are multiplied by, in Q27. Sums and products saturate.
+#[derive(Copy, Clone, Default, PartialEq)]
+pub struct mp3d_real_t(i32);
+
+/// The weights of the synthesis window, which are all integers.
+pub type mp3d_win_t = i32;
+
+/// Sums of the synthesis window in Q27, at the scale of 16-bit PCM.
+#[derive(Copy, Clone, Default)]
+pub struct mp3d_acc_t(i64);
+
+/// Scalefactors as `mant * 2^-(29 + shift)`, with `mant` normalised to `2^28..=2^29` so that the tiny Layer I/II
+/// scalefactors keep their precision.
+#[derive(Copy, Clone, Default)]
+pub struct mp3d_gain_t {
+    mant: i32,
+    shift: i32,
+}
+
+/// Values of `x^(4/3)` for the dequantiser, in Q16.
+pub type mp3d_pow43_t = i64;
+
+impl Add for mp3d_real_t {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for mp3d_real_t {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Neg for mp3d_real_t {
+    type Output = Self;
+
+    #[inline(always)]
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl Mul for mp3d_real_t {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        scale(self.0 as i64 * rhs.0 as i64, FRAC_BITS as i32)
+    }
+}
+
+impl Mul<mp3d_win_t> for mp3d_real_t {
+    type Output = mp3d_acc_t;
+
+    #[inline(always)]
+    fn mul(self, rhs: mp3d_win_t) -> mp3d_acc_t {
+        mp3d_acc_t(self.0 as i64 * rhs as i64)
+    }
+}
+
+impl AddAssign for mp3d_real_t {
+    #[inline(always)]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for mp3d_real_t {
+    #[inline(always)]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for mp3d_real_t {
+    #[inline(always)]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Add for mp3d_acc_t {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for mp3d_acc_t {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl AddAssign for mp3d_acc_t {
+    #[inline(always)]
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for mp3d_acc_t {
+    #[inline(always)]
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+/// Rounds half away from zero, for building constants.
+const fn round(x: f64) -> i64 {
+    (if x < 0. { x - 0.5 } else { x + 0.5 }) as i64
+}
+
+/// `x * 2^-shift` as Q27, rounded and saturated.
+#[inline(always)]
+fn scale(x: i64, shift: i32) -> mp3d_real_t {
+    let x = if shift <= 0 {
+        x.saturating_mul(1 << (-shift).min(62))
+    } else if shift <= 64 {
+        ((x >> (shift - 1)) + 1) >> 1
+    } else {
+        0
+    };
+    mp3d_real_t(x.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+}
+
+/// The SIMD kernels compute in `f32`, so are left out.
+#[cfg(not(feature = "forbid-unsafe"))]
+pub const HAVE_SIMD: bool = false;
+
+#[inline(always)]
+pub const fn real(x: f32) -> mp3d_real_t {
+    assert!(x.abs() < (1 << (31 - FRAC_BITS)) as f32, "constant out of range");
+    mp3d_real_t(round(x as f64 * (1 << FRAC_BITS) as f64) as i32)
+}
+
+pub const fn real_table<const N: usize>(x: [f32; N]) -> [mp3d_real_t; N] {
+    let mut table = [mp3d_real_t(0); N];
+    let mut i = 0;
+    while i < N {
+        table[i] = real(x[i]);
+        i += 1;
+    }
+    table
+}
+
+#[inline(always)]
+pub const fn win(x: f32) -> mp3d_win_t {
+    assert!(x as i32 as f32 == x, "synthesis window weights are integers");
+    x as i32
+}
+
+pub const fn win_table<const N: usize>(x: [f32; N]) -> [mp3d_win_t; N] {
+    let mut table = [0; N];
+    let mut i = 0;
+    while i < N {
+        table[i] = win(x[i]);
+        i += 1;
+    }
+    table
+}
+
+#[inline(always)]
+pub const fn acc_zero() -> mp3d_acc_t {
+    mp3d_acc_t(0)
+}
+
+pub const fn gain(x: f32) -> mp3d_gain_t {
+    if x == 0. {
+        return mp3d_gain_t { mant: 0, shift: 0 };
+    }
+    let mut m = x as f64;
+    let mut shift = 0;
+    while m > 1. {
+        m /= 2.;
+        shift -= 1;
+    }
+    while m < 0.5 {
+        m *= 2.;
+        shift += 1;
+    }
+    mp3d_gain_t { mant: round(m * (1 << 29) as f64) as i32, shift }
+}
+
+pub const fn gain_table<const N: usize>(x: [f32; N]) -> [mp3d_gain_t; N] {
+    let mut table = [mp3d_gain_t { mant: 0, shift: 0 }; N];
+    let mut i = 0;
+    while i < N {
+        table[i] = gain(x[i]);
+        i += 1;
+    }
+    table
+}
+
+pub const fn pow43_table<const N: usize>(x: [f32; N]) -> [mp3d_pow43_t; N] {
+    let mut table = [0; N];
+    let mut i = 0;
+    while i < N {
+        table[i] = round(x[i] as f64 * 65536.);
+        i += 1;
+    }
+    table
+}
+
+/// A gain as a coefficient, for intensity stereo.
+#[inline(always)]
+pub fn gain_real(g: mp3d_gain_t) -> mp3d_real_t {
+    scale(g.mant as i64, 29 - FRAC_BITS as i32 + g.shift)
+}
+
+/// `g * 2^n`
+#[inline(always)]
+pub fn gain_shl(g: mp3d_gain_t, n: u32) -> mp3d_gain_t {
+    mp3d_gain_t { mant: g.mant, shift: g.shift - n as i32 }
+}
+
+/// A Layer I/II sample code, before [`L12_dequant`] applies its scalefactor. It is held as a plain integer,
+/// as codes of up to 16 bits don't fit in Q27.
+#[inline(always)]
+pub fn L12_code(x: i32) -> mp3d_real_t {
+    mp3d_real_t(x)
+}
+
+#[inline(always)]
+pub fn L12_dequant(x: mp3d_real_t, scf: mp3d_gain_t) -> mp3d_real_t {
+    scale(x.0 as i64 * scf.mant as i64, 29 - FRAC_BITS as i32 + scf.shift)
+}
+
+#[inline(always)]
+pub fn L3_dequant(scf: mp3d_gain_t, x: mp3d_pow43_t) -> mp3d_real_t {
+    scale(x * scf.mant as i64, 16 + 29 - FRAC_BITS as i32 + scf.shift)
+}
+
+#[inline(always)]
+pub fn pcm_sample<S: Sealed>(a: mp3d_acc_t) -> S {
+    S::scale_fixed(a.0)
+}
+
+pub fn L3_ldexp_q2(y: mp3d_gain_t, exp_q2: i32) -> mp3d_gain_t {
+    let mut mant = ((y.mant as i64 * L3_LDEXP_Q2_G_EXPFRAC[(exp_q2 & 3) as usize] as i64 + (1 << 29)) >> 30) as i32;
+    let mut shift = y.shift + (exp_q2 >> 2);
+    if mant < 1 << 28 {
+        mant <<= 1;
+        shift += 1;
+    }
+    mp3d_gain_t { mant, shift }
+}
+
+/// As the float version, with the interpolation done in Q30.
+pub fn L3_pow_43(mut x: i32) -> mp3d_pow43_t {
+    let mut mult = 8;
+    if x < 129 {
+        return G_POW43[(16 + x) as usize];
+    }
+    if x < 1024 {
+        mult = 4;
+        x <<= 3;
+    }
+    let sign = (2 * x) & 64;
+    let frac = ((((x & 63) - sign) as i64) << 30) / ((x & !63) + sign) as i64;
+    // 4/3 and 2/9
+    let poly = (1 << 30) + ((frac * (1431655765 + ((frac * 238609294) >> 30))) >> 30);
+    (G_POW43[(16 + ((x + sign) >> 6)) as usize] * poly) >> (30 - mult)
+}
@@ -0,0 +1,147 @@
+//! The number types the decode pipeline computes in, as `f32` like upstream minimp3.
+//!
+//! [`crate::minimp3`] only uses the names defined here, so that `fixed.rs` can stand in for this module
+//! with the `fixed-point` feature.
+#![allow(
+    clippy::all,
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    unused_assignments
+)]
+
+use crate::sample::sealed::Sealed;
+
+use super::minimp3::tables::G_POW43;
+
+static L3_LDEXP_Q2_G_EXPFRAC: [f32; 4] = [
+    9.31322575e-10f32,
+    7.83145814e-10f32,
+    6.58544508e-10f32,
+    5.53767716e-10f32,
+];
+
+/// Spectral lines, subband samples and the coefficients they are multiplied by.
+pub type mp3d_real_t = f32;
+/// The weights of the synthesis window.
+pub type mp3d_win_t = f32;
+/// Sums of the synthesis window, at the scale of 16-bit PCM.
+pub type mp3d_acc_t = f32;
+/// Scalefactors, as multiplied into the dequantised spectrum.
+pub type mp3d_gain_t = f32;
+/// Values of `x^(4/3)` for the dequantiser.
+pub type mp3d_pow43_t = f32;
+
+/// Whether the SIMD kernels, which compute in `f32`, can stand in for the scalar loops.
+#[cfg(not(feature = "forbid-unsafe"))]
+pub const HAVE_SIMD: bool = true;
+
+pub const fn real(x: f32) -> mp3d_real_t {
+    x
+}
+
+pub const fn real_table<const N: usize>(x: [f32; N]) -> [mp3d_real_t; N] {
+    x
+}
+
+pub const fn win_table<const N: usize>(x: [f32; N]) -> [mp3d_win_t; N] {
+    x
+}
+
+pub const fn win(x: f32) -> mp3d_win_t {
+    x
+}
+
+pub const fn acc_zero() -> mp3d_acc_t {
+    0.
+}
+
+pub const fn gain(x: f32) -> mp3d_gain_t {
+    x
+}
+
+pub const fn gain_table<const N: usize>(x: [f32; N]) -> [mp3d_gain_t; N] {
+    x
+}
+
+pub const fn pow43_table<const N: usize>(x: [f32; N]) -> [mp3d_pow43_t; N] {
+    x
+}
+
+/// A gain as a coefficient, for intensity stereo.
+#[inline(always)]
+pub fn gain_real(g: mp3d_gain_t) -> mp3d_real_t {
+    g
+}
+
+/// `g * 2^n`
+#[inline(always)]
+pub fn gain_shl(g: mp3d_gain_t, n: u32) -> mp3d_gain_t {
+    g * ((1 as i32) << n) as f32
+}
+
+/// A Layer I/II sample code, before [`L12_dequant`] applies its scalefactor.
+#[inline(always)]
+pub fn L12_code(x: i32) -> mp3d_real_t {
+    x as f32
+}
+
+#[inline(always)]
+pub fn L12_dequant(x: mp3d_real_t, scf: mp3d_gain_t) -> mp3d_real_t {
+    x * scf
+}
+
+#[inline(always)]
+pub fn L3_dequant(scf: mp3d_gain_t, x: mp3d_pow43_t) -> mp3d_real_t {
+    scf * x
+}
+
+#[inline(always)]
+pub fn pcm_sample<S: Sealed>(a: mp3d_acc_t) -> S {
+    S::scale_pcm(a)
+}
+
+pub fn L3_ldexp_q2(
+    mut y: f32,
+    mut exp_q2: i32,
+) -> f32 {
+    let mut e: i32 = 0;
+    loop {
+        e = if 30 as i32 * 4 as i32 > exp_q2 {
+            exp_q2
+        } else {
+            30 as i32 * 4 as i32
+        };
+        y
+            *= L3_LDEXP_Q2_G_EXPFRAC[(e & 3 as i32) as usize]
+                * ((1 as i32) << 30 as i32 >> (e >> 2 as i32))
+                    as f32;
+        exp_q2 -= e;
+        if !(exp_q2 > 0 as i32) {
+            break;
+        }
+    }
+    return y;
+}
+
+pub fn L3_pow_43(mut x: i32) -> f32 {
+    let mut frac: f32 = 0.;
+    let mut sign: i32 = 0;
+    let mut mult: i32 = 256 as i32;
+    if x < 129 as i32 {
+        return G_POW43[(16 as i32 + x) as usize];
+    }
+    if x < 1024 as i32 {
+        mult = 16 as i32;
+        x <<= 3 as i32;
+    }
+    sign = 2 as i32 * x & 64 as i32;
+    frac = ((x & 63 as i32) - sign) as f32
+        / ((x & !(63 as i32)) + sign) as f32;
+    return G_POW43[(16 as i32 + (x + sign >> 6 as i32)) as usize]
+        * (1.0f32
+            + frac
+                * (4.0f32 / 3 as i32 as f32
+                    + frac * (2.0f32 / 9 as i32 as f32)))
+        * mult as f32;
+}
@@ -0,0 +1,81 @@
+use crate::{Decoder, FrameInfo, MAX_SAMPLES_PER_FRAME};
+
+/// Decodes the frames of an MP3 stream that is already in memory, keeping track of how much has been consumed.
+///
+/// The samples of each frame are borrowed from a buffer inside `Frames`, so frames are read with
+/// [`Frames::next_frame`] rather than through [`Iterator`], which can't lend out its own buffer.
+///
+/// ```
+/// # let mp3: &[u8] = &[];
+/// let mut frames = nanomp3::Frames::new(mp3);
+/// while let Some((info, pcm)) = frames.next_frame() {
+///     // `pcm` holds `info.samples_produced` interleaved samples for each channel
+/// }
+/// ```
+pub struct Frames<'a> {
+    mp3: &'a [u8],
+    offset: usize,
+    decoder: Decoder,
+    pcm: [f32; MAX_SAMPLES_PER_FRAME]
+}
+
+impl<'a> Frames<'a> {
+    /// Creates a `Frames` decoding `mp3` from the start.
+    pub fn new(mp3: &'a [u8]) -> Self {
+        Self::with_decoder(mp3, Decoder::new())
+    }
+
+    /// Creates a `Frames` with a decoder that has already been configured, e.g. with [`Decoder::set_gapless`].
+    pub fn with_decoder(mp3: &'a [u8], decoder: Decoder) -> Self {
+        Self {
+            mp3,
+            offset: 0,
+            decoder,
+            pcm: [0.; MAX_SAMPLES_PER_FRAME]
+        }
+    }
+
+    /// Returns the underlying decoder, e.g. to read [`Decoder::vbr_info`].
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+
+    /// Returns the underlying decoder mutably, e.g. to change its [`crate::CrcPolicy`].
+    pub fn decoder_mut(&mut self) -> &mut Decoder {
+        &mut self.decoder
+    }
+
+    /// Returns the offset in `mp3` of the data that hasn't been consumed yet.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the data that hasn't been consumed yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.mp3[self.offset..]
+    }
+
+    /// Decodes the next frame that produces samples, returning info about it and its interleaved samples,
+    /// or `None` at the end of the data.
+    ///
+    /// Junk, metadata and frames that can't be decoded are skipped over, as with [`Decoder::decode`].
+    /// The whole of the remaining data is always passed to the decoder, so a final frame that runs
+    /// exactly to the end of `mp3` is decoded even though no frame header follows it.
+    pub fn next_frame(&mut self) -> Option<(FrameInfo, &[f32])> {
+        let info = loop {
+            let mp3 = self.remaining();
+            if mp3.is_empty() {
+                return None;
+            }
+            let (consumed, result) = self.decoder.try_decode(mp3, &mut self.pcm);
+            self.offset += consumed;
+            if let Ok(info) = result {
+                if info.samples_produced != 0 {
+                    break info;
+                }
+            }
+        };
+        let n = info.samples_produced * usize::from(info.channels.num());
+        Some((info, &self.pcm[..n]))
+    }
+}
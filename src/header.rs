@@ -0,0 +1,173 @@
+use crate::{minimp3, Channels};
+
+/// The MPEG audio version of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Version {
+    /// MPEG-1 (ISO/IEC 11172-3)
+    Mpeg1,
+    /// MPEG-2 low sampling frequency extension (ISO/IEC 13818-3)
+    Mpeg2,
+    /// The unofficial MPEG-2.5 extension for very low sampling frequencies
+    Mpeg2_5
+}
+
+/// The MPEG audio layer of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    /// Layer I (MP1)
+    I = 1,
+    /// Layer II (MP2)
+    II,
+    /// Layer III (MP3)
+    III
+}
+
+/// The channel mode of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelMode {
+    Stereo,
+    /// Stereo coded with intensity and/or mid-side stereo, as given by [`FrameHeader::mode_extension`].
+    JointStereo,
+    /// Two independent mono channels.
+    DualChannel,
+    Mono
+}
+
+/// The de-emphasis to be applied to the decoded audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Emphasis {
+    None,
+    /// 50/15 µs emphasis
+    FiftyFifteen,
+    Reserved,
+    /// CCITT J.17 emphasis
+    CcittJ17
+}
+
+/// A parsed MPEG audio frame header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameHeader([u8; 4]);
+
+impl FrameHeader {
+    /// Parses the 4 bytes at the start of a frame, returning `None` if they are not a valid header.
+    pub fn parse(bytes: [u8; 4]) -> Option<Self> {
+        minimp3::hdr_valid(&bytes).then_some(Self(bytes))
+    }
+
+    /// Returns the raw bytes of the header.
+    pub fn to_bytes(self) -> [u8; 4] {
+        self.0
+    }
+
+    /// Returns the MPEG audio version.
+    pub fn version(&self) -> Version {
+        match self.0[1] >> 3 & 3 {
+            3 => Version::Mpeg1,
+            2 => Version::Mpeg2,
+            _ => Version::Mpeg2_5
+        }
+    }
+
+    /// Returns the MPEG audio layer.
+    pub fn layer(&self) -> Layer {
+        match self.0[1] >> 1 & 3 {
+            3 => Layer::I,
+            2 => Layer::II,
+            _ => Layer::III
+        }
+    }
+
+    /// Returns whether the frame is protected by a CRC-16 following the header.
+    pub fn is_protected(&self) -> bool {
+        self.0[1] & 1 == 0
+    }
+
+    /// Returns the bit rate in kilobits per second, or 0 for a free-format stream.
+    pub fn bitrate(&self) -> u32 {
+        minimp3::hdr_bitrate_kbps(&self.0)
+    }
+
+    /// Returns the sample rate in Hz.
+    pub fn sample_rate(&self) -> u32 {
+        minimp3::hdr_sample_rate_hz(&self.0)
+    }
+
+    /// Returns whether the frame carries an extra padding slot.
+    pub fn has_padding(&self) -> bool {
+        self.0[2] & 0x2 != 0
+    }
+
+    /// Returns the private bit, which has no meaning to the decoder.
+    pub fn private_bit(&self) -> bool {
+        self.0[2] & 0x1 != 0
+    }
+
+    /// Returns the channel mode.
+    pub fn channel_mode(&self) -> ChannelMode {
+        match self.0[3] >> 6 {
+            0 => ChannelMode::Stereo,
+            1 => ChannelMode::JointStereo,
+            2 => ChannelMode::DualChannel,
+            _ => ChannelMode::Mono
+        }
+    }
+
+    /// Returns the 2-bit mode extension. For Layer III joint stereo, bit 0 enables intensity stereo and
+    /// bit 1 enables mid-side stereo; for Layers I and II it selects the bound of intensity-coded subbands.
+    pub fn mode_extension(&self) -> u8 {
+        self.0[3] >> 4 & 3
+    }
+
+    /// Returns whether the frame is marked as copyrighted.
+    pub fn is_copyrighted(&self) -> bool {
+        self.0[3] & 0x8 != 0
+    }
+
+    /// Returns whether the frame is marked as the original rather than a copy.
+    pub fn is_original(&self) -> bool {
+        self.0[3] & 0x4 != 0
+    }
+
+    /// Returns the emphasis the audio was encoded with.
+    pub fn emphasis(&self) -> Emphasis {
+        match self.0[3] & 3 {
+            0 => Emphasis::None,
+            1 => Emphasis::FiftyFifteen,
+            2 => Emphasis::Reserved,
+            _ => Emphasis::CcittJ17
+        }
+    }
+
+    /// Returns the number of channels decoded from the frame.
+    pub fn channels(&self) -> Channels {
+        if self.channel_mode() == ChannelMode::Mono {
+            Channels::Mono
+        } else {
+            Channels::Stereo
+        }
+    }
+
+    /// Returns the number of samples per channel in the frame.
+    pub fn samples_per_frame(&self) -> usize {
+        minimp3::hdr_frame_samples(&self.0) as usize
+    }
+
+    /// Returns the length of the Layer III side info, which follows the header and any CRC.
+    pub(crate) fn side_info_bytes(&self) -> usize {
+        match (self.version(), self.channels()) {
+            (Version::Mpeg1, Channels::Mono) => 17,
+            (Version::Mpeg1, Channels::Stereo) => 32,
+            (_, Channels::Mono) => 9,
+            (_, Channels::Stereo) => 17
+        }
+    }
+
+    /// Returns the length of the frame in bytes, including the header and padding.
+    /// Returns `None` for a free-format stream, whose frame length can only be found by searching for the next header.
+    pub fn frame_bytes(&self) -> Option<usize> {
+        match minimp3::hdr_frame_bytes(&self.0, 0) {
+            0 => None,
+            n => Some(n + minimp3::hdr_padding(&self.0))
+        }
+    }
+}
@@ -0,0 +1,714 @@
+//! ID3v2 tags, which commonly precede the first frame of a stream.
+//!
+//! [`Tag`] parses ID3v2.2, ID3v2.3 and ID3v2.4 tags from a borrowed buffer without allocating.
+//! Unsynchronised tags must be parsed with [`Tag::parse_mut`], which undoes the unsynchronisation in place.
+
+use core::{char, fmt, slice, str};
+
+/// The length of an ID3v2 tag header, and of its optional footer.
+const HEADER_LEN: usize = 10;
+
+/// The header at the start of an ID3v2 tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TagHeader {
+    /// The major version, e.g. 3 for ID3v2.3.
+    pub major_version: u8,
+    pub revision: u8,
+    pub flags: u8,
+    /// The length of the tag after the header, excluding any footer.
+    pub size: usize
+}
+
+impl TagHeader {
+    /// Parses the header at the start of `data`, if there is one.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let &[b'I', b'D', b'3', major_version, revision, flags, s0, s1, s2, s3, ..] = data else {
+            return None;
+        };
+        // Only the versions that can be parsed, which also keeps "ID3" in audio data from passing for a tag
+        if !(2..=4).contains(&major_version) || revision == 0xff || (s0 | s1 | s2 | s3) & 0x80 != 0 {
+            return None;
+        }
+        Some(Self {
+            major_version,
+            revision,
+            flags,
+            size: syncsafe([s0, s1, s2, s3]) as usize
+        })
+    }
+
+    /// Whether the tag has had unsynchronisation applied, so that no false frame syncs appear within it.
+    pub fn is_unsynchronised(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    /// Whether an extended header precedes the frames. For ID3v2.2, this flag instead marks a compressed tag.
+    pub fn has_extended_header(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    /// Whether a footer follows the tag, which is only possible from ID3v2.4.
+    pub fn has_footer(&self) -> bool {
+        self.major_version >= 4 && self.flags & 0x10 != 0
+    }
+
+    /// The length of the whole tag, including the header and footer. The size field already counts
+    /// the bytes inserted by unsynchronisation, so it needs no adjustment for that flag.
+    pub fn tag_len(&self) -> usize {
+        HEADER_LEN + self.size + if self.has_footer() { HEADER_LEN } else { 0 }
+    }
+}
+
+/// Decodes a 28-bit integer stored 7 bits per byte.
+fn syncsafe(bytes: [u8; 4]) -> u32 {
+    bytes.into_iter().fold(0, |n, b| n << 7 | u32::from(b))
+}
+
+/// Returns the total length of the ID3v2 tag at the start of `data`, or `None` if it doesn't start with an ID3v2.2,
+/// ID3v2.3 or ID3v2.4 tag.
+///
+/// Only the first 10 bytes of the tag are needed, so this can be used to find out how much data to read
+/// before calling [`Tag::parse`].
+pub fn tag_len(data: &[u8]) -> Option<usize> {
+    TagHeader::parse(data).map(|header| header.tag_len())
+}
+
+/// Reverses unsynchronisation in place, removing the zero byte that follows every `0xFF`.
+/// Returns the length of the resynchronised data at the start of `data`.
+pub fn resynchronise(data: &mut [u8]) -> usize {
+    let mut len = 0;
+    for i in 0..data.len() {
+        if i == 0 || data[i] != 0 || data[i - 1] != 0xff {
+            data[len] = data[i];
+            len += 1;
+        }
+    }
+    len
+}
+
+/// The reasons a [`Tag`] could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The data doesn't start with an ID3v2 tag header.
+    NotATag,
+    /// The data ends before the end of the tag.
+    Truncated,
+    /// The tag is unsynchronised, so must be parsed with [`Tag::parse_mut`].
+    Unsynchronised,
+    /// The tag uses a feature that can't be parsed, such as ID3v2.2 compression.
+    Unsupported,
+    /// The tag's sizes are inconsistent, such as an extended header shorter than its own fields.
+    Malformed
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::NotATag => "not an ID3v2 tag",
+            Self::Truncated => "truncated ID3v2 tag",
+            Self::Unsynchronised => "unsynchronised ID3v2 tag",
+            Self::Unsupported => "unsupported ID3v2 tag",
+            Self::Malformed => "malformed ID3v2 tag"
+        })
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// An ID3v2 tag.
+#[derive(Debug, Clone, Copy)]
+pub struct Tag<'a> {
+    header: TagHeader,
+    /// The frames and padding, after any extended header.
+    frames: &'a [u8]
+}
+
+impl<'a> Tag<'a> {
+    /// Parses the tag at the start of `data`, which must contain the whole tag.
+    ///
+    /// Fails with [`Error::Unsynchronised`] if the tag or any of its frames are unsynchronised.
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        let (header, body) = Self::split(data)?;
+        if header.is_unsynchronised() {
+            return Err(Error::Unsynchronised);
+        }
+        let tag = Self::from_body(header, body)?;
+        if tag.frames().any(|frame| frame.is_unsynchronised()) {
+            return Err(Error::Unsynchronised);
+        }
+        Ok(tag)
+    }
+
+    /// Parses the tag at the start of `data`, which must contain the whole tag, undoing any unsynchronisation in place.
+    pub fn parse_mut(data: &'a mut [u8]) -> Result<Self, Error> {
+        let (mut header, _) = Self::split(data)?;
+        let body = &mut data[HEADER_LEN..HEADER_LEN + header.size];
+        let len = if header.major_version < 4 {
+            if header.is_unsynchronised() { resynchronise(body) } else { body.len() }
+        } else {
+            Self::resynchronise_frames(&header, body)?
+        };
+        header.flags &= !0x80;
+        let data: &'a [u8] = data;
+        let tag = Self::from_body(header, &data[HEADER_LEN..HEADER_LEN + len])?;
+        Ok(tag)
+    }
+
+    fn split(data: &[u8]) -> Result<(TagHeader, &[u8]), Error> {
+        let header = TagHeader::parse(data).ok_or(Error::NotATag)?;
+        let body = data.get(HEADER_LEN..HEADER_LEN + header.size).ok_or(Error::Truncated)?;
+        Ok((header, body))
+    }
+
+    fn from_body(header: TagHeader, body: &'a [u8]) -> Result<Self, Error> {
+        let frames = if !header.has_extended_header() {
+            body
+        } else if header.major_version == 2 {
+            return Err(Error::Unsupported);
+        } else {
+            &body[Self::extended_header_len(&header, body)?..]
+        };
+        Ok(Self { header, frames })
+    }
+
+    /// Returns the length of the extended header at the start of `body`, checked against the body.
+    fn extended_header_len(header: &TagHeader, body: &[u8]) -> Result<usize, Error> {
+        let size = body.get(..4).ok_or(Error::Truncated)?.try_into().unwrap();
+        let len = if header.major_version == 3 {
+            usize::try_from(u32::from_be_bytes(size)).ok().and_then(|n| n.checked_add(4)).ok_or(Error::Truncated)?
+        } else {
+            syncsafe(size) as usize
+        };
+        // Both versions have at least the size and the flags
+        if len < 6 {
+            return Err(Error::Malformed);
+        }
+        if len > body.len() {
+            return Err(Error::Truncated);
+        }
+        Ok(len)
+    }
+
+    /// Resynchronises each unsynchronised ID3v2.4 frame in place, moving the frames after it down to close the gap.
+    /// Returns the new length of `body`.
+    fn resynchronise_frames(header: &TagHeader, body: &mut [u8]) -> Result<usize, Error> {
+        let mut read = 0;
+        if header.has_extended_header() {
+            read = Self::extended_header_len(header, body)?;
+        }
+        let mut write = read;
+        while body.len().checked_sub(read).is_some_and(|rest| rest >= HEADER_LEN) && body[read] != 0 {
+            let size = syncsafe(body.get(read + 4..read + 8).ok_or(Error::Truncated)?.try_into().unwrap()) as usize;
+            let end = read + HEADER_LEN + size;
+            if end > body.len() {
+                return Err(Error::Truncated);
+            }
+            body.copy_within(read..read + HEADER_LEN, write);
+            let data_start = write + HEADER_LEN;
+            let mut len = size;
+            if header.is_unsynchronised() || body[write + 9] & 0x02 != 0 {
+                body.copy_within(read + HEADER_LEN..end, data_start);
+                len = resynchronise(&mut body[data_start..data_start + size]);
+                for (i, b) in body[write + 4..write + 8].iter_mut().enumerate() {
+                    *b = (len >> (7 * (3 - i))) as u8 & 0x7f;
+                }
+                body[write + 9] &= !0x02;
+            } else {
+                body.copy_within(read + HEADER_LEN..end, data_start);
+            }
+            read = end;
+            write = data_start + len;
+        }
+        Ok(write)
+    }
+
+    /// Returns the version of the tag as `(major, revision)`, e.g. `(4, 0)` for ID3v2.4.0.
+    pub fn version(&self) -> (u8, u8) {
+        (self.header.major_version, self.header.revision)
+    }
+
+    /// Returns the total length of the tag as stored, including the header and footer.
+    pub fn len(&self) -> usize {
+        self.header.tag_len()
+    }
+
+    /// Returns whether the tag has no frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames().next().is_none()
+    }
+
+    /// Returns an iterator over the frames of the tag.
+    pub fn frames(&self) -> Frames<'a> {
+        Frames {
+            major_version: self.header.major_version,
+            data: self.frames
+        }
+    }
+
+    /// Returns the first frame with the given ID. ID3v2.2 frames are found by their ID3v2.3 equivalents.
+    pub fn get(&self, id: &str) -> Option<Frame<'a>> {
+        self.frames().find(|frame| frame.id() == id)
+    }
+
+    /// Returns the text of the first text frame with the given ID.
+    pub fn text(&self, id: &str) -> Option<Text<'a>> {
+        match self.get(id)?.content() {
+            Content::Text(text) => Some(text),
+            _ => None
+        }
+    }
+
+    /// Returns the title (`TIT2`).
+    pub fn title(&self) -> Option<Text<'a>> {
+        self.text("TIT2")
+    }
+
+    /// Returns the lead artist (`TPE1`).
+    pub fn artist(&self) -> Option<Text<'a>> {
+        self.text("TPE1")
+    }
+
+    /// Returns the album (`TALB`).
+    pub fn album(&self) -> Option<Text<'a>> {
+        self.text("TALB")
+    }
+
+    /// Returns the track number, which may be followed by a slash and the number of tracks (`TRCK`).
+    pub fn track(&self) -> Option<Text<'a>> {
+        self.text("TRCK")
+    }
+}
+
+/// ID3v2.2 frame IDs and their ID3v2.3 equivalents.
+static V22_FRAME_IDS: [(&[u8; 3], &[u8; 4]); 24] = [
+    (b"BUF", b"RBUF"),
+    (b"CNT", b"PCNT"),
+    (b"COM", b"COMM"),
+    (b"PIC", b"APIC"),
+    (b"POP", b"POPM"),
+    (b"TAL", b"TALB"),
+    (b"TBP", b"TBPM"),
+    (b"TCM", b"TCOM"),
+    (b"TCO", b"TCON"),
+    (b"TCR", b"TCOP"),
+    (b"TEN", b"TENC"),
+    (b"TLE", b"TLEN"),
+    (b"TP1", b"TPE1"),
+    (b"TP2", b"TPE2"),
+    (b"TP3", b"TPE3"),
+    (b"TPA", b"TPOS"),
+    (b"TPB", b"TPUB"),
+    (b"TRK", b"TRCK"),
+    (b"TSS", b"TSSE"),
+    (b"TT2", b"TIT2"),
+    (b"TXX", b"TXXX"),
+    (b"TYE", b"TYER"),
+    (b"ULT", b"USLT"),
+    (b"WXX", b"WXXX")
+];
+
+/// An iterator over the frames of a [`Tag`].
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+    major_version: u8,
+    data: &'a [u8]
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Frame<'a>> {
+        let (id, size, flags, header_len) = if self.major_version == 2 {
+            let &[a, b, c, s0, s1, s2, ..] = self.data else { return None };
+            let id = V22_FRAME_IDS.iter()
+                .find(|(v22, _)| **v22 == [a, b, c])
+                .map_or([a, b, c, 0], |(_, v23)| **v23);
+            (id, u32::from_be_bytes([0, s0, s1, s2]), 0, 6)
+        } else {
+            let &[a, b, c, d, s0, s1, s2, s3, f0, f1, ..] = self.data else { return None };
+            let size = if self.major_version == 3 {
+                u32::from_be_bytes([s0, s1, s2, s3])
+            } else {
+                syncsafe([s0, s1, s2, s3])
+            };
+            ([a, b, c, d], size, u16::from_be_bytes([f0, f1]), 10)
+        };
+        // Padding, or a corrupt ID
+        if !id[..3].iter().chain(id[3..].iter().filter(|&&c| c != 0)).all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+            self.data = &[];
+            return None;
+        }
+        // ID3v2.3 sizes aren't syncsafe, so they can overflow the end of the frame on 32-bit targets
+        let Some(end) = (size as usize).checked_add(header_len) else {
+            self.data = &[];
+            return None;
+        };
+        let Some(data) = self.data.get(header_len..end) else {
+            self.data = &[];
+            return None;
+        };
+        self.data = &self.data[end..];
+        Some(Frame {
+            major_version: self.major_version,
+            id,
+            flags,
+            data
+        })
+    }
+}
+
+/// A frame of an ID3v2 tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    major_version: u8,
+    id: [u8; 4],
+    flags: u16,
+    data: &'a [u8]
+}
+
+impl<'a> Frame<'a> {
+    /// Returns the frame ID, such as `"TIT2"`. The IDs of common ID3v2.2 frames are translated to their
+    /// ID3v2.3 equivalents; other ID3v2.2 IDs are three characters long.
+    pub fn id(&self) -> &str {
+        let len = if self.id[3] == 0 { 3 } else { 4 };
+        str::from_utf8(&self.id[..len]).unwrap()
+    }
+
+    /// Only ID3v2.4 frames can be unsynchronised individually.
+    fn is_unsynchronised(&self) -> bool {
+        self.major_version == 4 && self.flags & 0x0002 != 0
+    }
+
+    /// Returns whether the frame is compressed, in which case its [`Frame::content`] can't be parsed.
+    pub fn is_compressed(&self) -> bool {
+        match self.major_version {
+            3 => self.flags & 0x0080 != 0,
+            4 => self.flags & 0x0008 != 0,
+            _ => false
+        }
+    }
+
+    /// Returns whether the frame is encrypted, in which case its [`Frame::content`] can't be parsed.
+    pub fn is_encrypted(&self) -> bool {
+        match self.major_version {
+            3 => self.flags & 0x0040 != 0,
+            4 => self.flags & 0x0004 != 0,
+            _ => false
+        }
+    }
+
+    /// Returns the data of the frame, after any extra header fields implied by its flags.
+    pub fn data(&self) -> &'a [u8] {
+        let extra = match self.major_version {
+            3 => [0x0080, 0x0040, 0x0020].iter()
+                .zip([4, 1, 1])
+                .filter(|(&flag, _)| self.flags & flag != 0)
+                .map(|(_, len)| len)
+                .sum(),
+            4 => [0x0040, 0x0004, 0x0001].iter()
+                .zip([1, 1, 4])
+                .filter(|(&flag, _)| self.flags & flag != 0)
+                .map(|(_, len)| len)
+                .sum(),
+            _ => 0
+        };
+        self.data.get(extra..).unwrap_or(&[])
+    }
+
+    /// Parses the content of the frame according to its ID.
+    pub fn content(&self) -> Content<'a> {
+        let data = self.data();
+        if self.is_compressed() || self.is_encrypted() {
+            return Content::Unknown(data);
+        }
+        let parsed = match &self.id {
+            b"TXXX" => encoded(data).map(|(encoding, data)| {
+                let (description, value) = split_terminated(encoding, data);
+                Content::UserText { description, value: Text::new(encoding, value).trim() }
+            }),
+            [b'T', ..] => encoded(data).map(|(encoding, data)| Content::Text(Text::new(encoding, data).trim())),
+            b"WXXX" => encoded(data).map(|(encoding, data)| {
+                let (description, url) = split_terminated(encoding, data);
+                Content::UserUrl { description, url: Text::new(Encoding::Latin1, url).trim() }
+            }),
+            [b'W', ..] => Some(Content::Url(Text::new(Encoding::Latin1, data).trim())),
+            b"COMM" | b"USLT" => encoded(data).and_then(|(encoding, data)| {
+                let (&language, data) = data.split_first_chunk()?;
+                let (description, text) = split_terminated(encoding, data);
+                let text = Text::new(encoding, text).trim();
+                Some(if self.id == *b"COMM" {
+                    Content::Comment { language, description, text }
+                } else {
+                    Content::Lyrics { language, description, text }
+                })
+            }),
+            b"APIC" => encoded(data).and_then(|(encoding, data)| {
+                let (mime_type, data) = if self.major_version == 2 {
+                    // A three character image format, e.g. "PNG"
+                    let (format, data) = data.split_at_checked(3)?;
+                    (Text::new(Encoding::Latin1, format), data)
+                } else {
+                    split_terminated(Encoding::Latin1, data)
+                };
+                let (&picture_type, data) = data.split_first()?;
+                let (description, data) = split_terminated(encoding, data);
+                Some(Content::Picture { mime_type, picture_type, description, data })
+            }),
+            _ => None
+        };
+        parsed.unwrap_or(Content::Unknown(data))
+    }
+}
+
+/// Splits the text encoding byte from the start of `data`.
+fn encoded(data: &[u8]) -> Option<(Encoding, &[u8])> {
+    let (&encoding, data) = data.split_first()?;
+    Some((Encoding::from_byte(encoding)?, data))
+}
+
+/// Splits a terminated string in `encoding` from the start of `data`, returning the string and the data after its terminator.
+fn split_terminated(encoding: Encoding, data: &[u8]) -> (Text<'_>, &[u8]) {
+    let unit = encoding.unit_len();
+    let end = data.chunks(unit)
+        .position(|c| c.iter().all(|&b| b == 0))
+        .map_or(data.len(), |i| i * unit);
+    let rest = data.get(end + unit..).unwrap_or(&[]);
+    (Text::new(encoding, &data[..end]), rest)
+}
+
+/// The parsed content of a [`Frame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Content<'a> {
+    /// A text frame, such as `TIT2`.
+    Text(Text<'a>),
+    /// A user defined text frame (`TXXX`).
+    UserText {
+        description: Text<'a>,
+        value: Text<'a>
+    },
+    /// A URL frame, such as `WOAR`.
+    Url(Text<'a>),
+    /// A user defined URL frame (`WXXX`).
+    UserUrl {
+        description: Text<'a>,
+        url: Text<'a>
+    },
+    /// A comment (`COMM`).
+    Comment {
+        /// The ISO-639-2 language code, e.g. `b"eng"`.
+        language: [u8; 3],
+        description: Text<'a>,
+        text: Text<'a>
+    },
+    /// Unsynchronised lyrics (`USLT`).
+    Lyrics {
+        /// The ISO-639-2 language code, e.g. `b"eng"`.
+        language: [u8; 3],
+        description: Text<'a>,
+        text: Text<'a>
+    },
+    /// An attached picture (`APIC`).
+    Picture {
+        /// The MIME type of the image, or for ID3v2.2 a three character format such as `PNG`.
+        mime_type: Text<'a>,
+        /// The kind of picture, e.g. 3 for the front cover.
+        picture_type: u8,
+        description: Text<'a>,
+        /// The image data.
+        data: &'a [u8]
+    },
+    /// A frame that isn't parsed, or that couldn't be parsed, with its raw data.
+    Unknown(&'a [u8])
+}
+
+/// The encoding of a [`Text`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// ISO-8859-1
+    Latin1,
+    /// UTF-16 starting with a byte order mark
+    Utf16,
+    /// UTF-16 big endian without a byte order mark
+    Utf16Be,
+    Utf8
+}
+
+impl Encoding {
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            0 => Some(Self::Latin1),
+            1 => Some(Self::Utf16),
+            2 => Some(Self::Utf16Be),
+            3 => Some(Self::Utf8),
+            _ => None
+        }
+    }
+
+    /// The length of a code unit, and so of a terminator.
+    fn unit_len(self) -> usize {
+        match self {
+            Self::Utf16 | Self::Utf16Be => 2,
+            Self::Latin1 | Self::Utf8 => 1
+        }
+    }
+}
+
+/// Encoded text borrowed from a [`Frame`], which is decoded on the fly by [`Text::chars`].
+///
+/// Text frames in ID3v2.4 may hold several values separated by terminators, which [`Text::values`] splits apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Text<'a> {
+    encoding: Encoding,
+    bytes: &'a [u8]
+}
+
+impl<'a> Text<'a> {
+    fn new(encoding: Encoding, bytes: &'a [u8]) -> Self {
+        Self { encoding, bytes }
+    }
+
+    /// Strips trailing terminators.
+    fn trim(mut self) -> Self {
+        let unit = self.encoding.unit_len();
+        while self.bytes.len() >= unit && self.bytes[self.bytes.len() - unit..].iter().all(|&b| b == 0) {
+            self.bytes = &self.bytes[..self.bytes.len() - unit];
+        }
+        self
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Returns the encoded bytes, including any byte order mark.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the text as a `str` if it can be borrowed without decoding, i.e. if it's valid UTF-8 or ASCII-only ISO-8859-1.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.encoding {
+            Encoding::Utf8 => str::from_utf8(self.bytes).ok(),
+            Encoding::Latin1 if self.bytes.is_ascii() => str::from_utf8(self.bytes).ok(),
+            _ => None
+        }
+    }
+
+    /// Returns an iterator decoding the characters of the text. Invalid sequences decode to `U+FFFD`.
+    pub fn chars(&self) -> Chars<'a> {
+        Chars(match self.encoding {
+            Encoding::Latin1 => CharsInner::Latin1(self.bytes.iter()),
+            Encoding::Utf8 => CharsInner::Utf8 {
+                chunks: self.bytes.utf8_chunks(),
+                valid: "".chars(),
+                invalid: false
+            },
+            Encoding::Utf16 | Encoding::Utf16Be => {
+                let (big_endian, bytes) = match self.bytes {
+                    [0xff, 0xfe, bytes @ ..] if self.encoding == Encoding::Utf16 => (false, bytes),
+                    [0xfe, 0xff, bytes @ ..] if self.encoding == Encoding::Utf16 => (true, bytes),
+                    bytes => (true, bytes)
+                };
+                CharsInner::Utf16(char::decode_utf16(Utf16Units {
+                    chunks: bytes.chunks_exact(2),
+                    big_endian
+                }))
+            }
+        })
+    }
+
+    /// Returns an iterator over the terminator-separated values of the text.
+    pub fn values(&self) -> Values<'a> {
+        Values(Some(*self))
+    }
+}
+
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chars().try_for_each(|c| fmt::Write::write_char(f, c))
+    }
+}
+
+impl PartialEq<str> for Text<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.chars().eq(other.chars())
+    }
+}
+
+impl PartialEq<&str> for Text<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+/// An iterator over the values of a [`Text`].
+#[derive(Debug, Clone)]
+pub struct Values<'a>(Option<Text<'a>>);
+
+impl<'a> Iterator for Values<'a> {
+    type Item = Text<'a>;
+
+    fn next(&mut self) -> Option<Text<'a>> {
+        let text = self.0.take()?;
+        let (value, rest) = split_terminated(text.encoding, text.bytes);
+        if value.bytes.len() < text.bytes.len() {
+            self.0 = Some(Text::new(text.encoding, rest));
+        }
+        Some(value)
+    }
+}
+
+/// An iterator over the characters of a [`Text`].
+#[derive(Debug, Clone)]
+pub struct Chars<'a>(CharsInner<'a>);
+
+#[derive(Debug, Clone)]
+enum CharsInner<'a> {
+    Latin1(slice::Iter<'a, u8>),
+    Utf8 {
+        chunks: str::Utf8Chunks<'a>,
+        valid: str::Chars<'a>,
+        invalid: bool
+    },
+    Utf16(char::DecodeUtf16<Utf16Units<'a>>)
+}
+
+#[derive(Debug, Clone)]
+struct Utf16Units<'a> {
+    chunks: slice::ChunksExact<'a, u8>,
+    big_endian: bool
+}
+
+impl Iterator for Utf16Units<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        let unit = self.chunks.next()?.try_into().unwrap();
+        Some(if self.big_endian { u16::from_be_bytes(unit) } else { u16::from_le_bytes(unit) })
+    }
+}
+
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match &mut self.0 {
+            CharsInner::Latin1(bytes) => bytes.next().map(|&b| char::from(b)),
+            CharsInner::Utf8 { chunks, valid, invalid } => loop {
+                if let Some(c) = valid.next() {
+                    return Some(c);
+                }
+                if *invalid {
+                    *invalid = false;
+                    return Some(char::REPLACEMENT_CHARACTER);
+                }
+                let chunk = chunks.next()?;
+                *valid = chunk.valid().chars();
+                *invalid = !chunk.invalid().is_empty();
+            },
+            CharsInner::Utf16(units) => units.next().map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        }
+    }
+}
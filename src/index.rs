@@ -0,0 +1,225 @@
+use core::fmt;
+
+use crate::{minimp3, DecodeError, Decoder, FrameHeader, FrameInfo, Layer, Version, PREROLL_SAMPLES};
+
+/// The length of the serialised index header: the first frame's header, then the number of frames.
+const HEADER_LEN: usize = 8;
+/// The length of each serialised frame: its offset in the top 48 bits and `main_data_begin` in the bottom 16.
+const ENTRY_LEN: usize = 8;
+
+/// An index of the audio frames in a stream, built by scanning the frame headers once, for sample-accurate
+/// random access with [`Decoder::seek_with_index`].
+///
+/// The index is stored in its serialised form in `Storage`, such as a `[u8; N]` or `&mut [u8]`,
+/// taking 8 bytes per frame. [`FrameIndex::as_bytes`] returns the serialised index, which can be stored
+/// alongside the stream and loaded again with [`FrameIndex::from_bytes`].
+///
+/// Every frame is assumed to hold as many samples as the first, which is the case for streams
+/// that don't change MPEG version or layer partway through.
+#[derive(Debug, Clone)]
+pub struct FrameIndex<Storage> {
+    storage: Storage,
+    len: usize
+}
+
+/// A frame recorded in a [`FrameIndex`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IndexedFrame {
+    /// The offset of the frame in the stream, as counted by [`Decoder::position`].
+    pub offset: u64,
+    /// The number of samples per channel in the stream before the frame, excluding any VBR header frame.
+    pub sample: u64,
+    /// How many bytes before the frame its main data begins, in the bit reservoir of earlier frames.
+    pub main_data_begin: u16
+}
+
+/// Returned by [`FrameIndex::skip_frame`] when the index storage has no room for another frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexFull;
+
+impl fmt::Display for IndexFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("frame index full")
+    }
+}
+
+impl core::error::Error for IndexFull {}
+
+impl<Storage: AsRef<[u8]>> FrameIndex<Storage> {
+    /// Loads an index serialised by [`FrameIndex::as_bytes`], returning `None` if it is invalid, such as when
+    /// its frames aren't in order.
+    /// `storage` may be longer than the serialised index, to leave room for more frames.
+    pub fn from_bytes(storage: Storage) -> Option<Self> {
+        let bytes = storage.as_ref();
+        let len = usize::try_from(u32::from_le_bytes(bytes.get(4..HEADER_LEN)?.try_into().unwrap())).ok()?;
+        // The length is untrusted, so the size it implies may not fit in a `usize`
+        let size = len.checked_mul(ENTRY_LEN).and_then(|n| n.checked_add(HEADER_LEN))?;
+        if bytes.len() < size
+            || len != 0 && FrameHeader::parse(bytes[..4].try_into().unwrap()).is_none()
+        {
+            return None;
+        }
+        // The offsets must increase for the distances between frames to make sense
+        let mut offsets = bytes[HEADER_LEN..size]
+            .chunks_exact(ENTRY_LEN)
+            .map(|entry| u64::from_le_bytes(entry.try_into().unwrap()) >> 16);
+        if offsets.clone().zip(offsets.by_ref().skip(1)).any(|(offset, next)| next <= offset) {
+            return None;
+        }
+        Some(Self { storage, len })
+    }
+
+    /// Returns the serialised index.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.storage.as_ref()[..HEADER_LEN + self.len * ENTRY_LEN]
+    }
+
+    /// Returns the underlying storage.
+    pub fn into_inner(self) -> Storage {
+        self.storage
+    }
+
+    /// Returns the number of frames in the index.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the index has no frames.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the header of the first frame, which gives the format of the stream.
+    pub fn header(&self) -> Option<FrameHeader> {
+        if self.is_empty() {
+            return None;
+        }
+        FrameHeader::parse(self.storage.as_ref()[..4].try_into().unwrap())
+    }
+
+    /// Returns the `i`th frame of the index.
+    pub fn get(&self, i: usize) -> Option<IndexedFrame> {
+        if i >= self.len {
+            return None;
+        }
+        let start = HEADER_LEN + i * ENTRY_LEN;
+        let entry = u64::from_le_bytes(self.storage.as_ref()[start..start + ENTRY_LEN].try_into().unwrap());
+        Some(IndexedFrame {
+            offset: entry >> 16,
+            sample: i as u64 * self.header()?.samples_per_frame() as u64,
+            main_data_begin: entry as u16
+        })
+    }
+
+    /// Returns the index of the frame holding `sample`, or the last frame if `sample` is past the end.
+    pub fn frame_at_sample(&self, sample: u64) -> Option<usize> {
+        let samples_per_frame = self.header()?.samples_per_frame() as u64;
+        Some((sample / samples_per_frame).min(self.len as u64 - 1) as usize)
+    }
+
+    /// Returns the frame to start decoding from so that the frame `i` can be decoded: the main data
+    /// of the frames in between must hold at least `main_data_begin` bytes for frame `i`.
+    fn reservoir_start(&self, i: usize) -> usize {
+        let Some(header) = self.header() else {
+            return i;
+        };
+        let overhead = 4 + if header.is_protected() { 2 } else { 0 } + header.side_info_bytes() as u64;
+        let needed = u64::from(self.get(i).map_or(0, |frame| frame.main_data_begin));
+        let mut start = i;
+        let mut main_data = 0;
+        while start > 0 && main_data < needed {
+            let frame_bytes = self.get(start).unwrap().offset - self.get(start - 1).unwrap().offset;
+            main_data += frame_bytes.saturating_sub(overhead);
+            start -= 1;
+        }
+        start
+    }
+}
+
+impl<Storage: AsRef<[u8]> + AsMut<[u8]>> FrameIndex<Storage> {
+    /// Creates an empty index in `storage`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `storage` is less than 8 bytes long.
+    pub fn new(mut storage: Storage) -> Self {
+        storage.as_mut()[..HEADER_LEN].fill(0);
+        Self { storage, len: 0 }
+    }
+
+    /// Returns the number of frames that can be added before the storage is full.
+    pub fn remaining_capacity(&self) -> usize {
+        (self.storage.as_ref().len() - HEADER_LEN) / ENTRY_LEN - self.len
+    }
+
+    /// Skips over the next frame with [`Decoder::skip_frame`], adding it to the index if it holds audio.
+    /// `decoder` should be scanning the stream from the start, so that the offsets and samples are counted from there.
+    ///
+    /// Returns `Err(IndexFull)` without consuming anything if there is no room for another frame.
+    pub fn skip_frame(&mut self, decoder: &mut Decoder, mp3: &[u8]) -> Result<(usize, Result<FrameInfo, DecodeError>), IndexFull> {
+        if self.remaining_capacity() == 0 {
+            return Err(IndexFull);
+        }
+        let (consumed, result) = decoder.skip_frame(mp3);
+        if let Ok(frame_info) = result {
+            let frame = &mp3[consumed - frame_info.frame_bytes..consumed];
+            let header = FrameHeader::parse(frame[..4].try_into().unwrap()).unwrap();
+            if self.is_empty() {
+                self.storage.as_mut()[..4].copy_from_slice(&frame[..4]);
+            }
+            let offset = decoder.position() - frame_info.frame_bytes as u64;
+            let entry = offset << 16 | u64::from(main_data_begin(header, frame));
+            let start = HEADER_LEN + self.len * ENTRY_LEN;
+            self.len += 1;
+            let bytes = self.storage.as_mut();
+            bytes[start..start + ENTRY_LEN].copy_from_slice(&entry.to_le_bytes());
+            bytes[4..HEADER_LEN].copy_from_slice(&(self.len as u32).to_le_bytes());
+        }
+        Ok((consumed, result))
+    }
+}
+
+/// Reads `main_data_begin` from the start of the side info of a Layer III frame.
+fn main_data_begin(header: FrameHeader, frame: &[u8]) -> u16 {
+    if header.layer() != Layer::III {
+        return 0;
+    }
+    let side_info = if header.is_protected() { 6 } else { 4 };
+    let Some(&[a, b]) = frame.get(side_info..side_info + 2) else {
+        return 0;
+    };
+    let bits = u16::from_be_bytes([a, b]);
+    if header.version() == Version::Mpeg1 { bits >> 7 } else { bits >> 8 }
+}
+
+impl Decoder {
+    /// Prepares to resume decoding at exactly `sample`, like [`Decoder::seek_to_sample`], but using `index` to
+    /// find where to start instead of restarting from the first frame. Returns the offset in the stream to read from next,
+    /// or `None` if the index is empty.
+    ///
+    /// Decoding starts far enough before the frame holding `sample` to prime the decoder, backing up further
+    /// to the frames holding the main data the first decoded frame refers back to with `main_data_begin`.
+    ///
+    /// The index doesn't hold the VBR header, so a decoder that hasn't read it counts samples from the first audio frame
+    /// and doesn't trim the stream when gapless decoding is enabled. Decode the first frame of the stream with the
+    /// decoder before seeking to pick it up.
+    pub fn seek_with_index<Storage: AsRef<[u8]>>(&mut self, index: &FrameIndex<Storage>, sample: u64) -> Option<u64> {
+        let header = index.header()?;
+        let first = index.get(0)?;
+        if self.first_frame.is_none() {
+            self.first_frame = Some((first.offset, header));
+        }
+        let samples_per_frame = header.samples_per_frame() as u64;
+        let target_frame = index.frame_at_sample(self.output_range().0.saturating_add(sample))?;
+        let preroll_frame = target_frame.saturating_sub(PREROLL_SAMPLES.div_ceil(samples_per_frame) as usize);
+        let start = index.get(index.reservoir_start(preroll_frame))?;
+
+        self.dec = minimp3::mp3dec_t::new();
+        self.position = start.offset;
+        self.sample = start.sample;
+        self.sample_exact = true;
+        self.seek_target = Some(sample);
+        self.update_output_range();
+        Some(self.position)
+    }
+}
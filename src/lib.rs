@@ -1,15 +1,71 @@
 #![no_std]
+#![cfg_attr(feature = "forbid-unsafe", forbid(unsafe_code))]
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::{fmt, time::Duration};
+
+use pcm::{Interleaved, Pcm, Planar};
 
 mod minimp3;
+#[cfg_attr(not(feature = "fixed-point"), path = "float.rs")]
+#[cfg_attr(feature = "fixed-point", path = "fixed.rs")]
+mod real;
+mod frames;
+mod header;
+pub mod id3;
+mod index;
+mod pcm;
+mod sample;
+#[cfg(feature = "std")]
+mod stream;
+mod vbr;
 
 #[cfg(test)]
 mod tests;
 
+pub use frames::Frames;
+pub use header::{ChannelMode, Emphasis, FrameHeader, Layer, Version};
+pub use index::{FrameIndex, IndexFull, IndexedFrame};
+pub use sample::{Sample, I24};
+#[cfg(feature = "std")]
+pub use stream::StreamDecoder;
+pub use vbr::{LameTag, ReplayGain, Vbri, VbrInfo, VbrKind};
+
 /// The minimum length of the PCM output buffer.
 pub const MAX_SAMPLES_PER_FRAME: usize = 1152*2;
 
+/// How many samples are decoded and discarded before a seek target. Decoding a granule depends on the one before
+/// through the overlap, and the synthesis filterbank depends on the 480 samples before.
+const PREROLL_SAMPLES: u64 = 1152;
+
+/// The furthest back `main_data_begin` can reach into the bit reservoir of earlier frames, in bytes.
+const MAX_MAIN_DATA_BEGIN: u64 = 511;
+
 /// The core MP3 decoder, with no internal buffering.
-pub struct Decoder(minimp3::mp3dec_t);
+pub struct Decoder {
+    dec: minimp3::mp3dec_t,
+    crc_policy: CrcPolicy,
+    vbr_info: Option<VbrInfo>,
+    gapless: bool,
+    /// The number of bytes consumed since the start of the stream.
+    position: u64,
+    /// The offset and header of the first frame of the stream, which may hold a VBR header.
+    first_frame: Option<(u64, FrameHeader)>,
+    /// The number of samples per channel in the stream before the next frame.
+    sample: u64,
+    /// Whether `sample` is exact, rather than estimated by [`Decoder::seek`].
+    sample_exact: bool,
+    /// The sample of the output to resume at after [`Decoder::seek_to_sample`], until the frame holding it is reached.
+    seek_target: Option<u64>,
+    /// Samples before this one are trimmed from the output.
+    output_start: u64,
+    /// Samples from this one on are trimmed from the output.
+    output_end: Option<u64>,
+    /// The scratch memory of the decoding methods that aren't given any, kept so it isn't zeroed on every call.
+    scratch: DecoderScratch
+}
 
 
 /// The channel formats that may be encoded in an MP3 frame.
@@ -27,7 +83,7 @@ impl Channels {
 }
 
 /// Information about the frame decoded by [`Decoder::decode`]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FrameInfo {
     /// The number of PCM samples produced.
     pub samples_produced: usize,
@@ -36,53 +92,463 @@ pub struct FrameInfo {
     /// Sample rate of this frame, in Hz.
     pub sample_rate: u32,
     /// The current MP3 bit rate, in kilobits per second.
-    pub bitrate: u32
+    pub bitrate: u32,
+    /// The length of the frame in bytes. The frame is at the end of the consumed bytes,
+    /// after any junk that was skipped to reach it.
+    pub frame_bytes: usize,
+    /// Whether the frame passed its CRC-16 check. Always `true` for frames without a CRC,
+    /// and for frames passed over by [`Decoder::skip_frame`].
+    pub crc_ok: bool
+}
+
+/// What [`Decoder`] does with frames that fail their CRC-16 check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrcPolicy {
+    /// Decode the frame anyway, only reporting the failure in [`FrameInfo::crc_ok`].
+    #[default]
+    Ignore,
+    /// Don't decode the frame, returning [`DecodeError::CrcMismatch`].
+    Drop,
+    /// Output silence in place of the frame, reporting the failure in [`FrameInfo::crc_ok`].
+    Conceal
+}
+
+/// The reasons [`Decoder::try_decode`] may produce no samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// `mp3` was empty.
+    InsufficientData,
+    /// No complete frame was found, and the consumed bytes were skipped as junk or an ID3v2 tag.
+    SkippedData,
+    /// A frame was found, but it depends on bit reservoir data from frames that weren't decoded.
+    /// This is expected for the first few frames of a stream that was joined partway through.
+    ReservoirNotFilled,
+    /// A frame was found, but its side info or bit allocation is invalid.
+    CorruptFrame,
+    /// A frame failed its CRC-16 check and was dropped, as requested by [`CrcPolicy::Drop`].
+    CrcMismatch,
+    /// The first frame held a Xing, Info or VBRI header rather than audio. It is available from [`Decoder::vbr_info`].
+    VbrHeader
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::InsufficientData => "insufficient data",
+            Self::SkippedData => "skipped data without a frame",
+            Self::ReservoirNotFilled => "bit reservoir not yet filled",
+            Self::CorruptFrame => "corrupt frame",
+            Self::CrcMismatch => "CRC mismatch",
+            Self::VbrHeader => "VBR header frame"
+        })
+    }
 }
 
+impl core::error::Error for DecodeError {}
+
 impl Decoder {
     /// Instantiates a `Decoder`.
     pub const fn new() -> Self {
-        Self(minimp3::mp3dec_t::new())
+        Self {
+            dec: minimp3::mp3dec_t::new(),
+            crc_policy: CrcPolicy::Ignore,
+            vbr_info: None,
+            gapless: false,
+            position: 0,
+            first_frame: None,
+            sample: 0,
+            sample_exact: true,
+            seek_target: None,
+            output_start: 0,
+            output_end: None,
+            scratch: DecoderScratch::new()
+        }
+    }
+
+    /// Returns the Xing, Info or VBRI header found in the first frame of the stream, if there was one.
+    ///
+    /// The frame holding it is reported as [`DecodeError::VbrHeader`] and produces no samples,
+    /// so the stream's duration is known exactly from [`VbrInfo::total_samples`] before any audio is decoded.
+    pub fn vbr_info(&self) -> Option<&VbrInfo> {
+        self.vbr_info.as_ref()
+    }
+
+    /// Returns whether gapless decoding is enabled.
+    pub fn gapless(&self) -> bool {
+        self.gapless
+    }
+
+    /// Enables or disables gapless decoding. The default is disabled.
+    ///
+    /// When enabled and the stream starts with a VBR header carrying a [`LameTag`], the encoder delay and
+    /// decoder delay are trimmed from the start of the output and the encoder padding from the end, so that
+    /// tracks play back to back without clicks. Frames that produce no samples still count towards the trimming.
+    ///
+    /// This takes effect when the VBR header is decoded, so should be set before decoding a stream.
+    pub fn set_gapless(&mut self, enabled: bool) {
+        self.gapless = enabled;
+    }
+
+    /// Returns the number of bytes consumed since the start of the stream, which is the offset of the next byte
+    /// the decoder expects. After [`Decoder::seek`], this is the offset it returned.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Prepares to resume decoding near `time`, returning the offset in the stream to read from next,
+    /// or `None` if no frame has been decoded yet or the stream is free-format.
+    ///
+    /// The offset is found from the seek table in the VBR header if there is one, or by assuming a constant bit rate
+    /// otherwise. It is only approximate, and is usually not at the start of a frame: the decoder searches
+    /// for the next frame as when starting a new stream. The bit reservoir and overlap are cleared, as
+    /// they hold data from before the seek, so the first frame or two after the offset may report
+    /// [`DecodeError::ReservoirNotFilled`] until the reservoir refills.
+    ///
+    /// A `time` past the end of the stream seeks to the end when the VBR header gives the length of the stream.
+    /// Otherwise `None` is returned if the offset is too large to represent.
+    ///
+    /// When gapless decoding is enabled, `time` is measured from the end of the trimmed encoder delay.
+    pub fn seek(&mut self, time: Duration) -> Option<u64> {
+        let (first_frame, first_header) = self.first_frame?;
+        let header = self.vbr_info.map_or(first_header, |vbr_info| vbr_info.header);
+        let rate = u64::from(header.sample_rate());
+        let (output_start, _) = self.output_range();
+        let samples = time.as_nanos() * u128::from(rate) / 1_000_000_000;
+        let mut target = u64::try_from(samples).unwrap_or(u64::MAX).saturating_add(output_start);
+        if let Some(total_samples) = self.vbr_info.and_then(|vbr_info| vbr_info.total_samples()) {
+            target = target.min(total_samples);
+        }
+
+        let (offset, sample) = match self.vbr_info.and_then(|vbr_info| vbr_info.seek_point(target)) {
+            Some(seek_point) => seek_point,
+            None => {
+                // Frames have the same length on average, which may not be a whole number of bytes
+                let audio_start = if self.vbr_info.is_some() { header.frame_bytes()? as u64 } else { 0 };
+                let samples_per_frame = header.samples_per_frame() as u64;
+                let frame = target / samples_per_frame;
+                let bytes_per_second = u64::from(header.bitrate()) * 125;
+                if bytes_per_second == 0 {
+                    return None;
+                }
+                let bytes = u128::from(frame * samples_per_frame) * u128::from(bytes_per_second) / u128::from(rate);
+                (audio_start.checked_add(bytes.try_into().ok()?)?, frame * samples_per_frame)
+            }
+        };
+        let position = first_frame.checked_add(offset)?;
+
+        self.dec = minimp3::mp3dec_t::new();
+        self.position = position;
+        self.sample = sample;
+        self.sample_exact = false;
+        self.seek_target = None;
+        self.output_start = output_start;
+        Some(self.position)
+    }
+
+    /// Prepares to resume decoding at exactly `sample`, counted per channel, returning the offset in the stream to read from next.
+    ///
+    /// When every frame of the stream has the same length, as in a constant bit rate stream whose bit rate needs
+    /// no padding, decoding resumes a few frames before the one holding `sample`: far enough back for the bit reservoir
+    /// to hold the 511 bytes that `main_data_begin` can refer back to, and for the pre-roll. Otherwise, if the decoder
+    /// is already before `sample`, decoding carries on from the current position, and if not it restarts from the
+    /// first frame. Restarting reads the whole stream up to `sample`, which [`Decoder::seek_with_index`] avoids.
+    /// Carrying on is only exact when the sample position is known: [`Decoder::seek`] only estimates it.
+    ///
+    /// The frames well before `sample` are passed over without being decoded, but their main data is kept
+    /// so that the bit reservoir holds everything the following frames refer back to with `main_data_begin`.
+    /// The frames just before the one holding `sample` are decoded and discarded to prime the overlap and
+    /// synthesis filterbank, and the output then starts exactly at `sample`.
+    ///
+    /// Frames passed over or discarded are still returned by [`Decoder::try_decode`], with no samples produced.
+    /// When gapless decoding is enabled, `sample` is counted from the end of the trimmed encoder delay.
+    pub fn seek_to_sample(&mut self, sample: u64) -> u64 {
+        self.seek_target = Some(sample);
+        let target = self.output_range().0.saturating_add(sample);
+        let carry_on = self.sample_exact && target >= self.sample;
+        let resume_at = match self.frame_before(target) {
+            // Jumping ahead only helps if the decoder isn't already past the frame
+            Some((offset, sample)) if !carry_on || self.sample < sample => Some((offset, sample)),
+            None if !carry_on => self.first_frame.map(|(first_frame, _)| (first_frame, 0)),
+            _ => None
+        };
+        if let Some((offset, sample)) = resume_at {
+            self.dec = minimp3::mp3dec_t::new();
+            self.position = offset;
+            self.sample = sample;
+            self.sample_exact = true;
+        }
+        self.update_output_range();
+        self.position
+    }
+
+    /// Returns how frames that fail their CRC-16 check are handled.
+    pub fn crc_policy(&self) -> CrcPolicy {
+        self.crc_policy
+    }
+
+    /// Sets how frames that fail their CRC-16 check are handled. The default is [`CrcPolicy::Ignore`].
+    pub fn set_crc_policy(&mut self, policy: CrcPolicy) {
+        self.crc_policy = policy;
     }
 
     /// Decode MP3 data into a buffer, returning the amount of MP3 data consumed and info about decoded samples.
+    /// The samples are written in any [`Sample`] format, such as `f32` or `i16`.
     /// `mp3` should contain at least several frames worth of data at any given time (16KiB recommended) to avoid artifacting.
     ///
-    /// Returns `(consumed_bytes, frame_info)`. When no frame can be decoded (insufficient data),
-    /// returns `(0, None)` so the caller knows to accumulate more data before retrying.
+    /// Returns `(consumed_bytes, frame_info)`. When no frame can be decoded, `frame_info` is `None`,
+    /// but `consumed_bytes` still covers any junk that was skipped or frame that was dropped.
+    /// If `mp3` is not empty, `consumed_bytes` is never zero, so a decoding loop always makes progress.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pcm` is less than [`MAX_SAMPLES_PER_FRAME`] long.
+    pub fn decode<S: Sample>(&mut self, mp3: &[u8], pcm: &mut [S]) -> (usize, Option<FrameInfo>) {
+        let (consumed, result) = self.try_decode(mp3, pcm);
+        (consumed, result.ok())
+    }
+
+    /// Decode MP3 data into a buffer like [`Decoder::decode`], but report why no samples were produced.
+    ///
+    /// Returns `(consumed_bytes, result)`. The consumed bytes are reported whether or not decoding succeeded,
+    /// and should be dropped from the front of `mp3` before the next call. As with [`Decoder::decode`],
+    /// `consumed_bytes` is only zero when `mp3` is empty.
     ///
     /// # Panics
     ///
     /// Panics if `pcm` is less than [`MAX_SAMPLES_PER_FRAME`] long.
-    pub fn decode(&mut self, mp3: &[u8], pcm: &mut [f32]) -> (usize, Option<FrameInfo>) {
+    pub fn try_decode<S: Sample>(&mut self, mp3: &[u8], pcm: &mut [S]) -> (usize, Result<FrameInfo, DecodeError>) {
         assert!(pcm.len() >= MAX_SAMPLES_PER_FRAME, "pcm buffer too small");
 
+        self.decode_frame(mp3, Some(&mut Interleaved(pcm)), None)
+    }
+
+    /// Decode MP3 data like [`Decoder::try_decode`], working in `scratch` instead of the decoder's own.
+    /// See [`DecoderScratch`] for the stack this needs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pcm` is less than [`MAX_SAMPLES_PER_FRAME`] long.
+    pub fn try_decode_with_scratch<S: Sample>(
+        &mut self,
+        mp3: &[u8],
+        pcm: &mut [S],
+        scratch: &mut DecoderScratch
+    ) -> (usize, Result<FrameInfo, DecodeError>) {
+        assert!(pcm.len() >= MAX_SAMPLES_PER_FRAME, "pcm buffer too small");
+
+        self.decode_frame(mp3, Some(&mut Interleaved(pcm)), Some(scratch))
+    }
+
+    /// Decode MP3 data like [`Decoder::decode`], but write the left and right channels to separate buffers.
+    /// The samples are written directly by the synthesis filterbank, so there is no pass to deinterleave them.
+    /// Mono streams are decoded to `left`, leaving `right` untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `left` or `right` is less than [`MAX_SAMPLES_PER_FRAME`] / 2 long.
+    pub fn decode_planar<S: Sample>(&mut self, mp3: &[u8], left: &mut [S], right: &mut [S]) -> (usize, Option<FrameInfo>) {
+        let (consumed, result) = self.try_decode_planar(mp3, left, right);
+        (consumed, result.ok())
+    }
+
+    /// Decode MP3 data into separate buffers for each channel like [`Decoder::decode_planar`], but report why no samples
+    /// were produced like [`Decoder::try_decode`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `left` or `right` is less than [`MAX_SAMPLES_PER_FRAME`] / 2 long.
+    pub fn try_decode_planar<S: Sample>(&mut self, mp3: &[u8], left: &mut [S], right: &mut [S]) -> (usize, Result<FrameInfo, DecodeError>) {
+        assert!(left.len() >= MAX_SAMPLES_PER_FRAME / 2 && right.len() >= MAX_SAMPLES_PER_FRAME / 2, "pcm buffer too small");
+
+        self.decode_frame(mp3, Some(&mut Planar(left, right)), None)
+    }
+
+    /// Decode MP3 data into separate buffers for each channel like [`Decoder::try_decode_planar`],
+    /// working in `scratch` instead of the decoder's own. See [`DecoderScratch`] for the stack this needs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `left` or `right` is less than [`MAX_SAMPLES_PER_FRAME`] / 2 long.
+    pub fn try_decode_planar_with_scratch<S: Sample>(
+        &mut self,
+        mp3: &[u8],
+        left: &mut [S],
+        right: &mut [S],
+        scratch: &mut DecoderScratch
+    ) -> (usize, Result<FrameInfo, DecodeError>) {
+        assert!(left.len() >= MAX_SAMPLES_PER_FRAME / 2 && right.len() >= MAX_SAMPLES_PER_FRAME / 2, "pcm buffer too small");
+
+        self.decode_frame(mp3, Some(&mut Planar(left, right)), Some(scratch))
+    }
+
+    /// Find the next frame and skip over it without decoding any audio, returning the amount of MP3 data consumed
+    /// and info about the frame. [`FrameInfo::samples_produced`] is the number of samples the frame would have decoded to.
+    ///
+    /// This only parses frame headers, so it is far faster than [`Decoder::try_decode`] for computing durations
+    /// or indexing a stream. The frames after a skipped frame may report [`DecodeError::ReservoirNotFilled`]
+    /// when decoded, as the skipped frame's data is not added to the bit reservoir.
+    pub fn skip_frame(&mut self, mp3: &[u8]) -> (usize, Result<FrameInfo, DecodeError>) {
+        self.decode_frame(mp3, None::<&mut Interleaved<f32>>, None)
+    }
+
+    fn decode_frame<P: Pcm>(
+        &mut self,
+        mp3: &[u8],
+        mut pcm: Option<&mut P>,
+        scratch: Option<&mut DecoderScratch>
+    ) -> (usize, Result<FrameInfo, DecodeError>) {
         let mut info = minimp3::mp3dec_frame_info_t::default();
 
-        let samples = unsafe { minimp3::mp3dec_decode_frame(
-            &mut self.0,
+        let pass_over = self.passes_over_next_frame();
+        if pass_over {
+            pcm = None;
+        }
+
+        let mut samples = minimp3::mp3dec_decode_frame(
+            &mut self.dec,
             mp3,
-            pcm,
-            &mut info
-        ) };
-
-        if samples != 0 {
-            (
-                info.frame_bytes.try_into().unwrap(),
-                Some(FrameInfo {
-                    samples_produced: samples.try_into().unwrap(),
-                    channels: match info.channels {
-                        1 => Channels::Mono,
-                        2 => Channels::Stereo,
-                        _ => unreachable!()
-                    },
-                    sample_rate: info.hz.try_into().unwrap(),
-                    bitrate: info.bitrate_kbps.try_into().unwrap()
-                })
-            )
+            pcm.as_deref_mut(),
+            &mut scratch.unwrap_or(&mut self.scratch).0,
+            &mut info,
+            self.crc_policy != CrcPolicy::Ignore,
+            pass_over,
+            // Only the first frame of the stream can be a VBR header, which is found again after seeking back to it
+            self.first_frame.is_none_or(|(first_frame, _)| first_frame == self.position)
+        );
+
+        if samples == minimp3::MP3D_E_CRC && self.crc_policy == CrcPolicy::Conceal {
+            samples = minimp3::hdr_frame_samples(&mp3[info.frame_offset..]) as i32;
+            if let Some(pcm) = pcm.as_deref_mut() {
+                pcm.silence(info.channels as usize, samples as usize);
+            }
+        }
+
+        if (samples > 0 || samples == minimp3::MP3D_VBR_TAG) && self.first_frame.is_none() {
+            let header = mp3[info.frame_offset..info.frame_offset + 4].try_into().unwrap();
+            self.first_frame = Some((self.position + info.frame_offset as u64, FrameHeader::parse(header).unwrap()));
+        }
+        self.position += info.frame_bytes as u64;
+
+        if matches!(samples, minimp3::MP3D_E_DECODE | minimp3::MP3D_E_CRC | minimp3::MP3D_E_RESERVOIR) {
+            // The frame's samples are lost, but their place in the stream still counts towards the trimming
+            self.trim_output::<P>(minimp3::hdr_frame_samples(&mp3[info.frame_offset..]) as usize, 0, None);
+        }
+
+        let result = match samples {
+            minimp3::MP3D_E_DECODE => Err(DecodeError::CorruptFrame),
+            minimp3::MP3D_E_CRC => Err(DecodeError::CrcMismatch),
+            minimp3::MP3D_E_RESERVOIR => Err(DecodeError::ReservoirNotFilled),
+            minimp3::MP3D_VBR_TAG => {
+                self.vbr_info = info.vbr_info;
+                self.sample = 0;
+                self.sample_exact = true;
+                self.update_output_range();
+                Err(DecodeError::VbrHeader)
+            }
+            0 if info.frame_bytes == 0 => Err(DecodeError::InsufficientData),
+            0 => Err(DecodeError::SkippedData),
+            _ => Ok(FrameInfo {
+                samples_produced: self.trim_output(samples.try_into().unwrap(), info.channels as usize, pcm),
+                channels: match info.channels {
+                    1 => Channels::Mono,
+                    2 => Channels::Stereo,
+                    _ => unreachable!()
+                },
+                sample_rate: info.hz.try_into().unwrap(),
+                bitrate: info.bitrate_kbps.try_into().unwrap(),
+                frame_bytes: info.frame_bytes - info.frame_offset,
+                crc_ok: info.crc_ok
+            })
+        };
+
+        (info.frame_bytes, result)
+    }
+
+    /// Returns the range of samples to output. When gapless decoding is enabled and the stream starts with a LAME tag,
+    /// this excludes the encoder delay and decoder delay at the start and the encoder padding at the end.
+    fn output_range(&self) -> (u64, Option<u64>) {
+        let gapless = self.vbr_info.filter(|_| self.gapless).and_then(|vbr_info| Some((vbr_info.lame?, vbr_info.total_samples()?)));
+        match gapless {
+            Some((lame, total_samples)) => {
+                let start = u64::from(lame.encoder_delay) + vbr::DECODER_DELAY;
+                let end = u64::from(lame.padding).saturating_sub(vbr::DECODER_DELAY);
+                (start, Some(total_samples.saturating_sub(end)))
+            }
+            None => (0, None)
+        }
+    }
+
+    /// Sets the output range for the stream, starting from the seek target if there is one.
+    fn update_output_range(&mut self) {
+        let (start, end) = self.output_range();
+        self.output_start = start.saturating_add(self.seek_target.unwrap_or(0));
+        self.output_end = end;
+    }
+
+    /// Returns the offset and sample of a frame far enough before `target` to fill the bit reservoir and prime the
+    /// decoder, if every frame of the stream has the same length so that the offset can be computed exactly.
+    fn frame_before(&self, target: u64) -> Option<(u64, u64)> {
+        let (first_frame, first_header) = self.first_frame?;
+        // Xing and VBRI headers mark variable bit rate streams, while Info headers mark constant ones
+        if self.vbr_info.is_some_and(|vbr_info| vbr_info.kind != VbrKind::Info) {
+            return None;
+        }
+        let header = self.vbr_info.map_or(first_header, |vbr_info| vbr_info.header);
+        let samples_per_frame = header.samples_per_frame() as u64;
+        // Frames are padded by a slot when the bit rate doesn't give a whole number of slots per frame
+        let slot_bytes = if header.layer() == Layer::I { 4 } else { 1 };
+        let frame_bits = samples_per_frame * u64::from(header.bitrate()) * 1000;
+        if header.has_padding() || !frame_bits.is_multiple_of(8 * slot_bytes * u64::from(header.sample_rate())) {
+            return None;
+        }
+        let frame_bytes = header.frame_bytes()? as u64;
+
+        let reservoir_frames = if header.layer() == Layer::III {
+            let overhead = 4 + if header.is_protected() { 2 } else { 0 } + header.side_info_bytes() as u64;
+            MAX_MAIN_DATA_BEGIN.div_ceil(frame_bytes.saturating_sub(overhead).max(1))
         } else {
-            (0, None)
+            0
+        };
+        let preroll_frames = PREROLL_SAMPLES.div_ceil(samples_per_frame);
+        let frame = (target / samples_per_frame).saturating_sub(reservoir_frames + preroll_frames);
+        if frame == 0 {
+            // The VBR header frame may hold main data for the first audio frame
+            return Some((first_frame, 0));
+        }
+        let audio_start = if self.vbr_info.is_some() { first_frame + frame_bytes } else { first_frame };
+        Some((audio_start.saturating_add(frame.saturating_mul(frame_bytes)), frame * samples_per_frame))
+    }
+
+    /// Returns whether the next frame is far enough before the seek target to be passed over without decoding.
+    fn passes_over_next_frame(&mut self) -> bool {
+        let (Some(seek_target), Some((_, header))) = (self.seek_target, self.first_frame) else {
+            return false;
+        };
+        let samples_per_frame = header.samples_per_frame() as u64;
+        let target = self.output_range().0.saturating_add(seek_target);
+        let target_frame = target - target % samples_per_frame;
+        if self.sample >= target_frame {
+            self.seek_target = None;
+            return false;
+        }
+        self.sample + samples_per_frame <= target_frame.saturating_sub(PREROLL_SAMPLES)
+    }
+
+    /// Trims the samples outside the output range from a frame of `samples` samples per channel, moving the samples kept
+    /// to the start of `pcm`. Returns the number of samples kept.
+    fn trim_output<P: Pcm>(&mut self, samples: usize, channels: usize, pcm: Option<&mut P>) -> usize {
+        let frame_start = self.sample;
+        self.sample += samples as u64;
+        let start = self.output_start.saturating_sub(frame_start).min(samples as u64) as usize;
+        let end = self.output_end.map_or(samples, |end| end.saturating_sub(frame_start).min(samples as u64) as usize);
+        if start >= end {
+            return 0;
+        }
+        if let Some(pcm) = pcm.filter(|_| start != 0) {
+            pcm.keep(channels, start..end);
         }
+        end - start
     }
 }
 
@@ -90,4 +556,35 @@ impl Default for Decoder {
     fn default() -> Self {
         Self::new()
     }
+}
+
+/// Scratch memory for decoding a frame, which is over 16 KiB.
+///
+/// Each [`Decoder`] holds one for its decoding methods, so that it isn't zeroed on every call. This makes a
+/// `Decoder` about 24 KiB, which is too much for the small task stacks of many embedded targets, so there it
+/// should be placed in a `static` or on the heap. The `_with_scratch` variants, such as
+/// [`Decoder::try_decode_with_scratch`], work in the caller's scratch instead. Either way, the rest of the call
+/// needs about 4 KiB of stack, as measured in release builds on x86_64. Debug builds need several times more.
+///
+/// Nothing is carried over between calls, so one `DecoderScratch` can be shared by several decoders that aren't
+/// decoding at the same time.
+pub struct DecoderScratch(minimp3::mp3dec_frame_scratch_t);
+
+impl DecoderScratch {
+    /// Creates zeroed scratch memory. This is a `const fn`, so that it can initialise a `static`.
+    pub const fn new() -> Self {
+        Self(minimp3::mp3dec_frame_scratch_t::new())
+    }
+}
+
+impl Default for DecoderScratch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DecoderScratch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecoderScratch").finish_non_exhaustive()
+    }
 }
\ No newline at end of file
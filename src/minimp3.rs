@@ -5,15 +5,18 @@
     unused_assignments
 )]
 
-mod tables;
+// The path is spelled out as the tests compile this file a second time, from another module
+#[path = "minimp3/tables.rs"]
+pub(crate) mod tables;
+#[cfg(not(feature = "forbid-unsafe"))]
+#[path = "minimp3/simd.rs"]
+pub(crate) mod simd;
 use core::iter;
 
-use tables::*;
+use crate::{id3, pcm::Pcm, VbrInfo};
 
-#[inline(always)]
-unsafe fn memcpy(dst: *mut (), src: *const (), count: usize) {
-    core::ptr::copy_nonoverlapping(src as *const u8, dst as *mut u8, count);
-}
+use super::real::*;
+use tables::*;
 
 #[derive(Copy, Clone, Default)]
 #[repr(C)]
@@ -24,44 +27,99 @@ pub struct mp3dec_frame_info_t {
     pub hz: i32,
     pub layer: u8,
     pub bitrate_kbps: i32,
+    pub crc_ok: bool,
+    pub vbr_info: Option<VbrInfo>,
 }
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct mp3dec_t {
-    mdct_overlap: [[f32; 288]; 2],
-    qmf_state: [f32; 960],
+    mdct_overlap: [[mp3d_real_t; 288]; 2],
+    qmf_state: [mp3d_real_t; 960],
     reserv: i32,
     free_format_bytes: usize,
     header: [u8; 4],
     reserv_buf: [u8; 511],
+    skip_bytes: usize,
+    /// Whether an ID3v2 tag may start here, at the start of the stream or straight after a previous tag.
+    tag_allowed: bool,
 }
 
 impl mp3dec_t {
     pub const fn new() -> Self {
         Self {
-            mdct_overlap: [[0.; 288]; 2],
-            qmf_state: [0.; 960],
+            mdct_overlap: [[const { real(0.) }; 288]; 2],
+            qmf_state: [const { real(0.) }; 960],
             reserv: 0,
             free_format_bytes: 0,
             header: [0; 4],
-            reserv_buf: [0; 511]
+            reserv_buf: [0; 511],
+            skip_bytes: 0,
+            tag_allowed: true
         }
     }
 }
 
-type mp3d_sample_t = f32;
+/// Returned by [`mp3dec_decode_frame`] when a frame was found but its side info or allocation is corrupt.
+pub const MP3D_E_DECODE: i32 = -5;
+/// Returned by [`mp3dec_decode_frame`] when a frame refers to bit reservoir data that hasn't been seen.
+pub const MP3D_E_RESERVOIR: i32 = -6;
+/// Returned by [`mp3dec_decode_frame`] when CRC verification is requested and a protected frame fails it.
+pub const MP3D_E_CRC: i32 = -7;
+/// Returned by [`mp3dec_decode_frame`] when VBR tag detection is requested and the first frame found holds a Xing,
+/// Info or VBRI header instead of audio.
+pub const MP3D_VBR_TAG: i32 = -8;
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 struct mp3dec_scratch_t {
-    grbuf: [[f32; 576]; 2],
-    scf: [f32; 40],
-    syn: [[f32; 64]; 33],
+    grbuf: [[mp3d_real_t; 576]; 2],
+    scf: [mp3d_gain_t; 40],
+    syn: [[mp3d_real_t; 64]; 33],
     ist_pos: [[u8; 39]; 2],
 }
+/// The buffers [`mp3dec_decode_frame`] works in, kept out of its stack frame as with `mp3dec_scratch_t` upstream.
+/// Nothing is carried over from one frame to the next.
+#[derive(Copy, Clone)]
+pub struct mp3dec_frame_scratch_t {
+    scratch: mp3dec_scratch_t,
+    maindata: [u8; 2815],
+    gr_info: [L3_gr_info_t; 4],
+}
+impl mp3dec_frame_scratch_t {
+    pub const fn new() -> Self {
+        Self {
+            scratch: mp3dec_scratch_t {
+                grbuf: [[const { real(0.) }; 576]; 2],
+                scf: [const { gain(0.) }; 40],
+                syn: [[const { real(0.) }; 64]; 33],
+                ist_pos: [[0; 39]; 2],
+            },
+            maindata: [0; 2815],
+            gr_info: [L3_gr_info_t {
+                sfbtab: &[],
+                part_23_length: 0,
+                big_values: 0,
+                scalefac_compress: 0,
+                global_gain: 0,
+                block_type: 0,
+                mixed_block_flag: 0,
+                n_long_sfb: 0,
+                n_short_sfb: 0,
+                table_select: [0; 3],
+                region_count: [0; 3],
+                subblock_gain: [0; 3],
+                preflag: 0,
+                scalefac_scale: 0,
+                count1_table: 0,
+                scfsi: 0,
+            }; 4],
+        }
+    }
+}
 #[derive(Copy, Clone)]
 #[repr(C)]
 struct L3_gr_info_t {
-    sfbtab: *const u8,
+    sfbtab: &'static [u8],
     part_23_length: u16,
     big_values: u16,
     scalefac_compress: u16,
@@ -80,6 +138,15 @@ struct L3_gr_info_t {
 }
 #[derive(Copy, Clone)]
 #[repr(C)]
+struct L12_scale_info {
+    scf: [mp3d_gain_t; 192],
+    total_bands: u8,
+    stereo_bands: u8,
+    bitalloc: [u8; 64],
+    scfcod: [u8; 64],
+}
+#[derive(Copy, Clone)]
+#[repr(C)]
 struct bs_t<'a> {
     buf: &'a [u8],
     pos: i32,
@@ -97,32 +164,25 @@ fn bs_init(
 }
 
 fn get_bits(bs: &mut bs_t, n: i32) -> u32 {
-    let mut next: u32 = 0;
-    let mut cache: u32 = 0 as i32 as u32;
-    let s: u32 = (bs.pos & 7 as i32) as u32;
-    let mut shl: i32 = (n as u32).wrapping_add(s) as i32;
-    let mut p = &bs.buf[(bs.pos >> 3) as usize..];
+    let s = bs.pos & 7;
+    let mut shl = n + s;
     bs.pos += n;
     if bs.pos > bs.limit {
-        return 0 as i32 as u32;
+        return 0;
     }
-    let fresh0 = p;
-    p = &p[1..];
-    next = (fresh0[0] as i32 & 255 as i32 >> s) as u32;
-    loop {
-        shl -= 8 as i32;
-        if !(shl > 0 as i32) {
-            break;
-        }
+    let mut bytes = bs.buf[((bs.pos - n) >> 3) as usize..].iter();
+    let mut next = (bytes.next().unwrap() & 0xff >> s) as u32;
+    let mut cache = 0;
+    shl -= 8;
+    while shl > 0 {
         cache |= next << shl;
-        let fresh1 = p;
-        p = &p[1..];
-        next = fresh1[0] as u32;
+        next = *bytes.next().unwrap() as u32;
+        shl -= 8;
     }
-    return cache | next >> -shl;
+    cache | next >> -shl
 }
 
-fn hdr_valid(h: &[u8]) -> bool {
+pub fn hdr_valid(h: &[u8]) -> bool {
     h[0] == 0xff &&
         (h[1] & 0xf0 == 0xf0 || h[1] & 0xfe == 0xe2) &&
         h[1] >> 1 & 3 != 0 &&
@@ -140,20 +200,25 @@ fn hdr_compare(
         (h1[2] & 0xf0 == 0) == (h2[2] & 0xf0 == 0)
 }
 
-fn hdr_bitrate_kbps(h: &[u8]) -> u32 {
+pub fn hdr_bitrate_kbps(h: &[u8]) -> u32 {
     2 * (HDR_BITRATE_KBPS_HALFRATE
         [(h[1] & 0x8 != 0) as usize]
         [((h[1] >> 1 & 3) - 1) as usize]
         [(h[2] >> 4) as usize] as u32)
 }
 
-fn hdr_sample_rate_hz(h: &[u8]) -> u32 {
+pub fn hdr_sample_rate_hz(h: &[u8]) -> u32 {
     HDR_SAMPLE_RATE_HZ_G_HZ[(h[2] >> 2 & 3) as usize]
         >> (h[1] & 0x8 == 0) as u32
         >> (h[1] & 0x10 == 0) as u32
 }
 
-fn hdr_frame_samples(h: &[u8]) -> u32 {
+/// The sample rate as an index from 0 to 8, counting up from the MPEG-2.5 rates to the MPEG-1 ones.
+fn hdr_my_sample_rate(h: &[u8]) -> usize {
+    (h[2] >> 2 & 3) as usize + ((h[1] >> 3 & 1) + (h[1] >> 4 & 1)) as usize * 3
+}
+
+pub fn hdr_frame_samples(h: &[u8]) -> u32 {
     if h[1] & 6 == 6 {
         384
     } else {
@@ -161,7 +226,7 @@ fn hdr_frame_samples(h: &[u8]) -> u32 {
     }
 }
 
-fn hdr_frame_bytes(
+pub fn hdr_frame_bytes(
     h: &[u8],
     free_format_size: usize,
 ) -> usize {
@@ -175,7 +240,7 @@ fn hdr_frame_bytes(
     if frame_bytes != 0 { frame_bytes } else { free_format_size }
 }
 
-fn hdr_padding(h: &[u8]) -> usize {
+pub fn hdr_padding(h: &[u8]) -> usize {
     if h[2] & 0x2 != 0 {
         if h[1] & 6 == 6 {
             4
@@ -187,660 +252,502 @@ fn hdr_padding(h: &[u8]) -> usize {
     }
 }
 
+fn L12_subband_alloc_table(
+    hdr: &[u8],
+    sci: &mut L12_scale_info,
+) -> &'static [[u8; 3]] {
+    let mode = hdr[3] >> 6;
+    let stereo_bands: u8 = if mode == 3 {
+        0
+    } else if mode == 1 {
+        ((hdr[3] >> 4 & 3) << 2) + 4
+    } else {
+        32
+    };
+    let (alloc, nbands): (&'static [[u8; 3]], u8) = if hdr[1] & 6 == 6 {
+        (&L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L1, 32)
+    } else if hdr[1] & 0x8 == 0 {
+        (&L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L2M2, 30)
+    } else {
+        let sample_rate_idx = hdr[2] >> 2 & 3;
+        let mut kbps = hdr_bitrate_kbps(hdr) >> (mode != 3) as u32;
+        if kbps == 0 {
+            // free-format
+            kbps = 192;
+        }
+        if kbps < 56 {
+            (
+                &L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L2M1_LOWRATE,
+                if sample_rate_idx == 2 { 12 } else { 8 },
+            )
+        } else if kbps >= 96 && sample_rate_idx != 1 {
+            (&L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L2M1, 30)
+        } else {
+            (&L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L2M1, 27)
+        }
+    };
+    sci.total_bands = nbands;
+    sci.stereo_bands = stereo_bands.min(nbands);
+    alloc
+}
+
+fn L12_read_scalefactors(
+    bs: &mut bs_t,
+    pba: &[u8],
+    scfcod: &[u8],
+    bands: usize,
+    scf: &mut [mp3d_gain_t],
+) {
+    let mut scf = scf.iter_mut();
+    for i in 0..bands {
+        let mut s: mp3d_gain_t = const { gain(0.) };
+        let ba = pba[i] as usize;
+        let mask: u8 = if ba != 0 { 4 + (19 >> scfcod[i] & 3) } else { 0 };
+        let mut m: u8 = 4;
+        while m != 0 {
+            if mask & m != 0 {
+                let b = get_bits(bs, 6);
+                s = gain_shl(L12_READ_SCALEFACTORS_G_DEQ_L12[ba * 3 - 6 + (b % 3) as usize], 21 - b / 3);
+            }
+            *scf.next().unwrap() = s;
+            m >>= 1;
+        }
+    }
+}
+
+fn L12_read_scale_info(
+    hdr: &[u8],
+    bs: &mut bs_t,
+    sci: &mut L12_scale_info,
+) -> i32 {
+    let mut subband_alloc = L12_subband_alloc_table(hdr, sci).iter();
+    let mut k: usize = 0;
+    let mut ba_bits: i32 = 0;
+    let mut ba_code_tab: &[u8] = &L12_READ_SCALE_INFO_G_BITALLOC_CODE_TAB;
+    for i in 0..sci.total_bands as usize {
+        if i == k {
+            let &[tab_offset, code_tab_width, band_count] = subband_alloc.next().unwrap();
+            k += band_count as usize;
+            ba_bits = code_tab_width as i32;
+            ba_code_tab = &L12_READ_SCALE_INFO_G_BITALLOC_CODE_TAB[tab_offset as usize..];
+        }
+        let mut ba = ba_code_tab[get_bits(bs, ba_bits) as usize];
+        sci.bitalloc[2 * i] = ba;
+        if i < sci.stereo_bands as usize {
+            ba = ba_code_tab[get_bits(bs, ba_bits) as usize];
+        }
+        sci.bitalloc[2 * i + 1] = if sci.stereo_bands != 0 { ba } else { 0 };
+    }
+    for i in 0..2 * sci.total_bands as usize {
+        sci.scfcod[i] = if sci.bitalloc[i] != 0 {
+            if hdr[1] & 6 == 6 { 2 } else { get_bits(bs, 2) as u8 }
+        } else {
+            6
+        };
+    }
+    // The CRC covers everything up to the scalefactors
+    let crc_end = bs.pos;
+    L12_read_scalefactors(
+        bs,
+        &sci.bitalloc,
+        &sci.scfcod,
+        2 * sci.total_bands as usize,
+        &mut sci.scf,
+    );
+    for i in sci.stereo_bands as usize..sci.total_bands as usize {
+        sci.bitalloc[2 * i + 1] = 0;
+    }
+    crc_end
+}
+
+fn L12_dequantize_granule(
+    grbuf: &mut [mp3d_real_t],
+    bs: &mut bs_t,
+    sci: &L12_scale_info,
+    group_size: usize,
+) -> usize {
+    let mut choff: isize = 576;
+    for j in 0..4 {
+        let mut dst = (group_size * j) as isize;
+        for i in 0..2 * sci.total_bands as usize {
+            let ba = sci.bitalloc[i] as u32;
+            if ba != 0 {
+                let dst = &mut grbuf[dst as usize..][..group_size];
+                if ba < 17 {
+                    let half: i32 = (1 << (ba - 1)) - 1;
+                    for x in dst {
+                        *x = L12_code(get_bits(bs, ba as i32) as i32 - half);
+                    }
+                } else {
+                    // 3, 5, 9
+                    let modulus: u32 = (2 << (ba - 17)) + 1;
+                    // 5, 7, 10
+                    let mut code = get_bits(bs, (modulus + 2 - (modulus >> 3)) as i32);
+                    for x in dst {
+                        *x = L12_code((code % modulus) as i32 - (modulus / 2) as i32);
+                        code /= modulus;
+                    }
+                }
+            }
+            dst += choff;
+            choff = 18 - choff;
+        }
+    }
+    group_size * 4
+}
+
+fn L12_apply_scf_384(
+    sci: &L12_scale_info,
+    scf: &[mp3d_gain_t],
+    dst: &mut [mp3d_real_t],
+) {
+    let stereo = sci.stereo_bands as usize * 18;
+    let total = sci.total_bands as usize * 18;
+    dst.copy_within(stereo..total, 576 + stereo);
+    for (i, band) in dst[..total].chunks_exact_mut(18).enumerate() {
+        for x in &mut band[..12] {
+            *x = L12_dequant(*x, scf[6 * i]);
+        }
+    }
+    for (i, band) in dst[576..576 + total].chunks_exact_mut(18).enumerate() {
+        for x in &mut band[..12] {
+            *x = L12_dequant(*x, scf[6 * i + 3]);
+        }
+    }
+}
+
+/// CRC-16 with polynomial 0x8005, as used to protect frames, over the first `nbits` bits of `data`.
+pub fn mp3d_crc16(
+    mut crc: u16,
+    data: &[u8],
+    nbits: usize,
+) -> u16 {
+    for i in 0..nbits {
+        let bit = (data[i / 8] >> (7 - i % 8) & 1) as u16;
+        let msb = crc >> 15;
+        crc <<= 1;
+        if msb ^ bit != 0 {
+            crc ^= 0x8005;
+        }
+    }
+    crc
+}
+
+fn mp3d_check_crc(
+    hdr: &[u8],
+    crc: i32,
+    nbits: i32,
+) -> bool {
+    crc < 0 || {
+        let header_crc = mp3d_crc16(0xffff, &hdr[2..4], 16);
+        mp3d_crc16(header_crc, &hdr[6..], nbits as usize) == crc as u16
+    }
+}
+
 fn L3_read_side_info(
     bs: &mut bs_t,
-    mut gr: &mut [L3_gr_info_t],
+    gr: &mut [L3_gr_info_t],
     hdr: &[u8],
 ) -> i32 {
-    let mut tables: u32 = 0;
-    let mut scfsi: u32 = 0 as i32 as u32;
-    let mut main_data_begin: i32 = 0;
-    let mut part_23_sum: i32 = 0 as i32;
-    let mut sr_idx: i32 = (hdr[2] as i32
-        >> 2 as i32 & 3 as i32)
-        + ((hdr[1] as i32 >> 3 as i32
-            & 1 as i32)
-            + (hdr[1] as i32 >> 4 as i32
-                & 1 as i32)) * 3 as i32;
-    sr_idx -= (sr_idx != 0 as i32) as i32;
-    let mut gr_count: i32 = if hdr[3] & 0xc0 == 0xc0 {
-        1
-    } else {
-        2
-    };
-    if hdr[1] & 0x8 != 0 {
-        gr_count *= 2 as i32;
-        main_data_begin = get_bits(bs, 9 as i32) as i32;
-        scfsi = get_bits(bs, 7 as i32 + gr_count);
+    let mpeg1 = hdr[1] & 0x8 != 0;
+    let mono = hdr[3] & 0xc0 == 0xc0;
+    let mut sr_idx = hdr_my_sample_rate(hdr);
+    sr_idx -= (sr_idx != 0) as usize;
+    let mut gr_count = if mono { 1 } else { 2 };
+    let mut scfsi = 0;
+    let main_data_begin;
+    if mpeg1 {
+        gr_count *= 2;
+        main_data_begin = get_bits(bs, 9) as i32;
+        scfsi = get_bits(bs, 7 + gr_count);
     } else {
-        main_data_begin = (get_bits(bs, 8 as i32 + gr_count) >> gr_count)
-            as i32;
+        main_data_begin = (get_bits(bs, 8 + gr_count) >> gr_count) as i32;
     }
-    loop {
-        if hdr[3] & 0xc0 == 0xc0 {
-            scfsi <<= 4 as i32;
+    let mut part_23_sum = 0;
+    for gr in &mut gr[..gr_count as usize] {
+        if mono {
+            scfsi <<= 4;
         }
-        gr[0].part_23_length = get_bits(bs, 12 as i32) as u16;
-        part_23_sum += gr[0].part_23_length as i32;
-        gr[0].big_values = get_bits(bs, 9 as i32) as u16;
-        if gr[0].big_values as i32 > 288 as i32 {
-            return -(1 as i32);
+        gr.part_23_length = get_bits(bs, 12) as u16;
+        part_23_sum += gr.part_23_length as i32;
+        gr.big_values = get_bits(bs, 9) as u16;
+        if gr.big_values > 288 {
+            return -1;
         }
-        gr[0].global_gain = get_bits(bs, 8 as i32) as u8;
-        gr[0]
-            .scalefac_compress = get_bits(
-            bs,
-            if hdr[1] & 0x8 != 0 {
-                4 as i32
-            } else {
-                9 as i32
-            },
-        ) as u16;
-        gr[0].sfbtab = (L3_READ_SIDE_INFO_G_SCF_LONG[sr_idx as usize]).as_ptr();
-        gr[0].n_long_sfb = 22 as i32 as u8;
-        gr[0].n_short_sfb = 0 as i32 as u8;
-        if get_bits(bs, 1 as i32) != 0 {
-            gr[0].block_type = get_bits(bs, 2 as i32) as u8;
-            if gr[0].block_type == 0 {
-                return -(1 as i32);
+        gr.global_gain = get_bits(bs, 8) as u8;
+        gr.scalefac_compress = get_bits(bs, if mpeg1 { 4 } else { 9 }) as u16;
+        gr.sfbtab = &L3_READ_SIDE_INFO_G_SCF_LONG[sr_idx];
+        gr.n_long_sfb = 22;
+        gr.n_short_sfb = 0;
+        let tables;
+        if get_bits(bs, 1) != 0 {
+            gr.block_type = get_bits(bs, 2) as u8;
+            if gr.block_type == 0 {
+                return -1;
             }
-            gr[0].mixed_block_flag = get_bits(bs, 1 as i32) as u8;
-            gr[0].region_count[0 as i32 as usize] = 7 as i32 as u8;
-            gr[0]
-                .region_count[1 as i32 as usize] = 255 as i32 as u8;
-            if gr[0].block_type as i32 == 2 as i32 {
-                scfsi &= 0xf0f as i32 as u32;
-                if gr[0].mixed_block_flag == 0 {
-                    gr[0]
-                        .region_count[0 as i32
-                        as usize] = 8 as i32 as u8;
-                    gr[0].sfbtab = (L3_READ_SIDE_INFO_G_SCF_SHORT[sr_idx as usize]).as_ptr();
-                    gr[0].n_long_sfb = 0 as i32 as u8;
-                    gr[0].n_short_sfb = 39 as i32 as u8;
+            gr.mixed_block_flag = get_bits(bs, 1) as u8;
+            gr.region_count[0] = 7;
+            gr.region_count[1] = 255;
+            if gr.block_type == 2 {
+                scfsi &= 0xf0f;
+                if gr.mixed_block_flag == 0 {
+                    gr.region_count[0] = 8;
+                    gr.sfbtab = &L3_READ_SIDE_INFO_G_SCF_SHORT[sr_idx];
+                    gr.n_long_sfb = 0;
+                    gr.n_short_sfb = 39;
                 } else {
-                    gr[0].sfbtab = (L3_READ_SIDE_INFO_G_SCF_MIXED[sr_idx as usize]).as_ptr();
-                    gr[0]
-                        .n_long_sfb = (if hdr[1] & 0x8 != 0 {
-                        8 as i32
-                    } else {
-                        6 as i32
-                    }) as u8;
-                    gr[0].n_short_sfb = 30 as i32 as u8;
+                    gr.sfbtab = &L3_READ_SIDE_INFO_G_SCF_MIXED[sr_idx];
+                    gr.n_long_sfb = if mpeg1 { 8 } else { 6 };
+                    gr.n_short_sfb = 30;
                 }
             }
-            tables = get_bits(bs, 10 as i32);
-            tables <<= 5 as i32;
-            gr[0]
-                .subblock_gain[0 as i32
-                as usize] = get_bits(bs, 3 as i32) as u8;
-            gr[0]
-                .subblock_gain[1 as i32
-                as usize] = get_bits(bs, 3 as i32) as u8;
-            gr[0]
-                .subblock_gain[2 as i32
-                as usize] = get_bits(bs, 3 as i32) as u8;
+            tables = get_bits(bs, 10) << 5;
+            for subblock_gain in &mut gr.subblock_gain {
+                *subblock_gain = get_bits(bs, 3) as u8;
+            }
         } else {
-            gr[0].block_type = 0 as i32 as u8;
-            gr[0].mixed_block_flag = 0 as i32 as u8;
-            tables = get_bits(bs, 15 as i32);
-            gr[0]
-                .region_count[0 as i32
-                as usize] = get_bits(bs, 4 as i32) as u8;
-            gr[0]
-                .region_count[1 as i32
-                as usize] = get_bits(bs, 3 as i32) as u8;
-            gr[0]
-                .region_count[2 as i32 as usize] = 255 as i32 as u8;
+            gr.block_type = 0;
+            gr.mixed_block_flag = 0;
+            tables = get_bits(bs, 15);
+            gr.region_count[0] = get_bits(bs, 4) as u8;
+            gr.region_count[1] = get_bits(bs, 3) as u8;
+            gr.region_count[2] = 255;
         }
-        gr[0]
-            .table_select[0 as i32
-            as usize] = (tables >> 10 as i32) as u8;
-        gr[0]
-            .table_select[1 as i32
-            as usize] = (tables >> 5 as i32 & 31 as i32 as u32)
-            as u8;
-        gr[0]
-            .table_select[2 as i32
-            as usize] = (tables & 31 as i32 as u32) as u8;
-        gr[0]
-            .preflag = (if hdr[1] & 0x8 != 0 {
-            get_bits(bs, 1 as i32)
+        gr.table_select = [(tables >> 10) as u8, (tables >> 5 & 31) as u8, (tables & 31) as u8];
+        gr.preflag = if mpeg1 {
+            get_bits(bs, 1) as u8
         } else {
-            (gr[0].scalefac_compress as i32 >= 500 as i32) as i32
-                as u32
-        }) as u8;
-        gr[0].scalefac_scale = get_bits(bs, 1 as i32) as u8;
-        gr[0].count1_table = get_bits(bs, 1 as i32) as u8;
-        gr[0]
-            .scfsi = (scfsi >> 12 as i32 & 15 as i32 as u32)
-            as u8;
-        scfsi <<= 4 as i32;
-        gr = &mut gr[1..];
-        gr_count -= 1;
-        if !(gr_count != 0) {
-            break;
-        }
+            (gr.scalefac_compress >= 500) as u8
+        };
+        gr.scalefac_scale = get_bits(bs, 1) as u8;
+        gr.count1_table = get_bits(bs, 1) as u8;
+        gr.scfsi = (scfsi >> 12 & 15) as u8;
+        scfsi <<= 4;
     }
     if part_23_sum + bs.pos > bs.limit + main_data_begin * 8 {
-        return -(1 as i32);
+        return -1;
     }
-    return main_data_begin;
+    main_data_begin
 }
-unsafe fn L3_read_scalefactors(
-    mut scf: *mut u8,
-    mut ist_pos: *mut u8,
-    scf_size: *const u8,
-    scf_count: *const u8,
+
+fn L3_read_scalefactors(
+    mut scf: &mut [u8],
+    mut ist_pos: &mut [u8],
+    scf_size: &[u8; 4],
+    scf_count: &[u8],
     bitbuf: &mut bs_t,
     mut scfsi: i32,
 ) {
-    let mut i: i32 = 0;
-    let mut k: i32 = 0;
-    i = 0 as i32;
-    while i < 4 as i32 && *scf_count.offset(i as isize) as i32 != 0 {
-        let cnt: i32 = *scf_count.offset(i as isize) as i32;
-        if scfsi & 8 as i32 != 0 {
-            memcpy(
-                scf as *mut (),
-                ist_pos as *const (),
-                cnt as usize,
-            );
+    for (&bits, &cnt) in iter::zip(scf_size, scf_count).take_while(|&(_, &cnt)| cnt != 0) {
+        let cnt = cnt as usize;
+        if scfsi & 8 != 0 {
+            scf[..cnt].copy_from_slice(&ist_pos[..cnt]);
+        } else if bits == 0 {
+            scf[..cnt].fill(0);
+            ist_pos[..cnt].fill(0);
         } else {
-            let bits: i32 = *scf_size.offset(i as isize) as i32;
-            if bits == 0 {
-                core::ptr::write_bytes(scf, 0, cnt as usize);
-                core::ptr::write_bytes(ist_pos, 0, cnt as usize);
-            } else {
-                let max_scf: i32 = if scfsi < 0 as i32 {
-                    ((1 as i32) << bits) - 1 as i32
-                } else {
-                    -(1 as i32)
-                };
-                k = 0 as i32;
-                while k < cnt {
-                    let s: i32 = get_bits(bitbuf, bits) as i32;
-                    *ist_pos
-                        .offset(
-                            k as isize,
-                        ) = (if s == max_scf { -(1 as i32) } else { s })
-                        as u8;
-                    *scf.offset(k as isize) = s as u8;
-                    k += 1;
-                }
+            let max_scf = if scfsi < 0 { (1 << bits) - 1 } else { -1 };
+            for (scf, ist_pos) in iter::zip(&mut scf[..cnt], &mut ist_pos[..cnt]) {
+                let s = get_bits(bitbuf, bits as i32) as i32;
+                *ist_pos = (if s == max_scf { -1 } else { s }) as u8;
+                *scf = s as u8;
             }
         }
-        ist_pos = ist_pos.offset(cnt as isize);
-        scf = scf.offset(cnt as isize);
-        i += 1;
-        scfsi *= 2 as i32;
-    }
-    let ref mut fresh2 = *scf.offset(2 as i32 as isize);
-    *fresh2 = 0 as i32 as u8;
-    let ref mut fresh3 = *scf.offset(1 as i32 as isize);
-    *fresh3 = *fresh2;
-    *scf.offset(0 as i32 as isize) = *fresh3;
-}
-fn L3_ldexp_q2(
-    mut y: f32,
-    mut exp_q2: i32,
-) -> f32 {
-    let mut e: i32 = 0;
-    loop {
-        e = if 30 as i32 * 4 as i32 > exp_q2 {
-            exp_q2
-        } else {
-            30 as i32 * 4 as i32
-        };
-        y
-            *= L3_LDEXP_Q2_G_EXPFRAC[(e & 3 as i32) as usize]
-                * ((1 as i32) << 30 as i32 >> (e >> 2 as i32))
-                    as f32;
-        exp_q2 -= e;
-        if !(exp_q2 > 0 as i32) {
-            break;
-        }
+        ist_pos = &mut ist_pos[cnt..];
+        scf = &mut scf[cnt..];
+        scfsi *= 2;
     }
-    return y;
+    scf[..3].fill(0);
 }
-unsafe fn L3_decode_scalefactors(
+
+fn L3_decode_scalefactors(
     hdr: &[u8],
-    ist_pos: *mut u8,
+    ist_pos: &mut [u8],
     bs: &mut bs_t,
     gr: &L3_gr_info_t,
-    scf: *mut f32,
+    scf: &mut [mp3d_gain_t],
     ch: u32,
 ) {
-    let mut scf_partition: *const u8 = (L3_DECODE_SCALEFACTORS_G_SCM_PARTITIONS[(((*gr).n_short_sfb != 0)
-        as i32 + ((*gr).n_long_sfb == 0) as i32) as usize])
-        .as_ptr();
-    let mut scf_size: [u8; 4] = [0; 4];
-    let mut iscf: [u8; 40] = [0; 40];
-    let mut i: i32 = 0;
-    let scf_shift: i32 = (*gr).scalefac_scale as i32
-        + 1 as i32;
-    let mut gain_exp: i32 = 0;
-    let mut scfsi: i32 = (*gr).scfsi as i32;
-    let mut gain: f32 = 0.;
+    // The dequantizer output is scaled down by 2, as in minimp3
+    const BITS_DEQUANTIZER_OUT: i32 = -1;
+    const MAX_SCFI: i32 = (255 + BITS_DEQUANTIZER_OUT * 4 - 210 + 3) & !3;
+
+    let mut scf_partition: &[u8] =
+        &L3_DECODE_SCALEFACTORS_G_SCM_PARTITIONS[(gr.n_short_sfb != 0) as usize + (gr.n_long_sfb == 0) as usize];
+    let mut scf_size = [0u8; 4];
+    let mut iscf = [0u8; 40];
+    let scf_shift = gr.scalefac_scale as i32 + 1;
+    let mut scfsi = gr.scfsi as i32;
     if hdr[1] & 0x8 != 0 {
-        let part: i32 = L3_DECODE_SCALEFACTORS_G_SCFC_DECODE[(*gr).scalefac_compress as usize]
-            as i32;
-        scf_size[0 as i32 as usize] = (part >> 2 as i32) as u8;
-        scf_size[1 as i32 as usize] = scf_size[0 as i32 as usize];
-        scf_size[2 as i32 as usize] = (part & 3 as i32) as u8;
-        scf_size[3 as i32 as usize] = scf_size[2 as i32 as usize];
+        let part = L3_DECODE_SCALEFACTORS_G_SCFC_DECODE[gr.scalefac_compress as usize];
+        scf_size = [part >> 2, part >> 2, part & 3, part & 3];
     } else {
-        let mut k: i32 = 0;
-        let mut modprod: i32 = 0;
-        let mut sfc: i32 = 0;
-        let ist: i32 = (hdr[3] & 0x10 != 0 && ch != 0) as i32;
-        sfc = (*gr).scalefac_compress as i32 >> ist;
-        k = ist * 3 as i32 * 4 as i32;
-        while sfc >= 0 as i32 {
-            modprod = 1 as i32;
-            i = 3 as i32;
-            while i >= 0 as i32 {
-                scf_size[i
-                    as usize] = (sfc / modprod % L3_DECODE_SCALEFACTORS_G_MOD[(k + i) as usize] as i32)
-                    as u8;
-                modprod *= L3_DECODE_SCALEFACTORS_G_MOD[(k + i) as usize] as i32;
-                i -= 1;
+        let ist = (hdr[3] & 0x10 != 0 && ch != 0) as usize;
+        let mut sfc = (gr.scalefac_compress >> ist) as i32;
+        let mut k = ist * 3 * 4;
+        while sfc >= 0 {
+            let mut modprod = 1;
+            for i in (0..4).rev() {
+                let modulus = L3_DECODE_SCALEFACTORS_G_MOD[k + i] as i32;
+                scf_size[i] = (sfc / modprod % modulus) as u8;
+                modprod *= modulus;
             }
             sfc -= modprod;
-            k += 4 as i32;
+            k += 4;
         }
-        scf_partition = scf_partition.offset(k as isize);
-        scfsi = -(16 as i32);
+        scf_partition = &scf_partition[k..];
+        scfsi = -16;
     }
-    L3_read_scalefactors(
-        iscf.as_mut_ptr(),
-        ist_pos,
-        scf_size.as_mut_ptr(),
-        scf_partition,
-        bs,
-        scfsi,
-    );
-    if (*gr).n_short_sfb != 0 {
-        let sh: i32 = 3 as i32 - scf_shift;
-        i = 0 as i32;
-        while i < (*gr).n_short_sfb as i32 {
-            iscf[((*gr).n_long_sfb as i32 + i + 0 as i32)
-                as usize] = (iscf[((*gr).n_long_sfb as i32 + i
-                + 0 as i32) as usize] as i32
-                + (((*gr).subblock_gain[0 as i32 as usize] as i32)
-                    << sh)) as u8;
-            iscf[((*gr).n_long_sfb as i32 + i + 1 as i32)
-                as usize] = (iscf[((*gr).n_long_sfb as i32 + i
-                + 1 as i32) as usize] as i32
-                + (((*gr).subblock_gain[1 as i32 as usize] as i32)
-                    << sh)) as u8;
-            iscf[((*gr).n_long_sfb as i32 + i + 2 as i32)
-                as usize] = (iscf[((*gr).n_long_sfb as i32 + i
-                + 2 as i32) as usize] as i32
-                + (((*gr).subblock_gain[2 as i32 as usize] as i32)
-                    << sh)) as u8;
-            i += 3 as i32;
+    L3_read_scalefactors(&mut iscf, ist_pos, &scf_size, scf_partition, bs, scfsi);
+    let n_long_sfb = gr.n_long_sfb as usize;
+    let n_sfb = n_long_sfb + gr.n_short_sfb as usize;
+    if gr.n_short_sfb != 0 {
+        let sh = 3 - scf_shift;
+        for window in iscf[n_long_sfb..n_sfb].chunks_exact_mut(3) {
+            for (x, &subblock_gain) in iter::zip(window, &gr.subblock_gain) {
+                *x = x.wrapping_add(subblock_gain << sh);
+            }
         }
-    } else if (*gr).preflag != 0 {
-        i = 0 as i32;
-        while i < 10 as i32 {
-            iscf[(11 as i32 + i)
-                as usize] = (iscf[(11 as i32 + i) as usize] as i32
-                + L3_DECODE_SCALEFACTORS_G_PREAMP[i as usize] as i32) as u8;
-            i += 1;
+    } else if gr.preflag != 0 {
+        for (x, &preamp) in iter::zip(&mut iscf[11..], &L3_DECODE_SCALEFACTORS_G_PREAMP) {
+            *x = x.wrapping_add(preamp);
         }
     }
-    gain_exp = (*gr).global_gain as i32 + -(1 as i32) * 4 as i32
-        - 210 as i32
-        - (if hdr[3] & 0xe0 == 0x60 {
-            2 as i32
-        } else {
-            0 as i32
-        });
-    gain = L3_ldexp_q2(
-        ((1 as i32)
-            << (255 as i32 + -(1 as i32) * 4 as i32
-                - 210 as i32 + 3 as i32 & !(3 as i32))
-                / 4 as i32) as f32,
-        (255 as i32 + -(1 as i32) * 4 as i32 - 210 as i32
-            + 3 as i32 & !(3 as i32)) - gain_exp,
-    );
-    i = 0 as i32;
-    while i < (*gr).n_long_sfb as i32 + (*gr).n_short_sfb as i32 {
-        *scf
-            .offset(
-                i as isize,
-            ) = L3_ldexp_q2(gain, (iscf[i as usize] as i32) << scf_shift);
-        i += 1;
+    let gain_exp = gr.global_gain as i32 + BITS_DEQUANTIZER_OUT * 4 - 210
+        - if hdr[3] & 0xe0 == 0x60 { 2 } else { 0 };
+    let gr_gain = L3_ldexp_q2(const { gain((1 << (MAX_SCFI / 4)) as f32) }, MAX_SCFI - gain_exp);
+    for (scf, &iscf) in iter::zip(&mut scf[..n_sfb], &iscf) {
+        *scf = L3_ldexp_q2(gr_gain, (iscf as i32) << scf_shift);
     }
 }
 
-fn L3_pow_43(mut x: i32) -> f32 {
-    let mut frac: f32 = 0.;
-    let mut sign: i32 = 0;
-    let mut mult: i32 = 256 as i32;
-    if x < 129 as i32 {
-        return G_POW43[(16 as i32 + x) as usize];
-    }
-    if x < 1024 as i32 {
-        mult = 16 as i32;
-        x <<= 3 as i32;
-    }
-    sign = 2 as i32 * x & 64 as i32;
-    frac = ((x & 63 as i32) - sign) as f32
-        / ((x & !(63 as i32)) + sign) as f32;
-    return G_POW43[(16 as i32 + (x + sign >> 6 as i32)) as usize]
-        * (1.0f32
-            + frac
-                * (4.0f32 / 3 as i32 as f32
-                    + frac * (2.0f32 / 9 as i32 as f32)))
-        * mult as f32;
-}
-unsafe fn L3_huffman(
-    mut dst: *mut f32,
+fn L3_huffman(
+    dst: &mut [mp3d_real_t],
     bs: &mut bs_t,
     gr_info: &L3_gr_info_t,
-    mut scf: *const f32,
+    scf: &[mp3d_gain_t],
     layer3gr_limit: i32,
 ) {
-    let mut one: f32 = 0.0f32;
-    let mut ireg: i32 = 0 as i32;
-    let mut big_val_cnt: i32 = (*gr_info).big_values as i32;
-    let mut sfb: *const u8 = (*gr_info).sfbtab;
-    let mut bs_next_ptr = &bs.buf[(bs.pos / 8 as i32) as usize..];
-    let mut bs_cache: u32 = (bs_next_ptr[0] as u32)
-        .wrapping_mul(256 as u32)
-        .wrapping_add(bs_next_ptr[1] as u32)
-        .wrapping_mul(256 as u32)
-        .wrapping_add(bs_next_ptr[2] as u32)
-        .wrapping_mul(256 as u32)
-        .wrapping_add(bs_next_ptr[3] as u32)
-        << (bs.pos & 7 as i32);
-    let mut pairs_to_decode: i32 = 0;
-    let mut np: i32 = 0;
-    let mut bs_sh: i32 = (bs.pos & 7 as i32) - 8 as i32;
-    bs_next_ptr = &bs_next_ptr[4..];
-    while big_val_cnt > 0 as i32 {
-        let tab_num: i32 = (*gr_info).table_select[ireg as usize]
-            as i32;
-        let fresh4 = ireg;
-        ireg = ireg + 1;
-        let mut sfb_cnt: i32 = (*gr_info).region_count[fresh4 as usize]
-            as i32;
-        let codebook: *const i16 = L3_HUFFMAN_TABS
-            .as_ptr()
-            .offset(L3_HUFFMAN_TABINDEX[tab_num as usize] as i32 as isize);
-        let linbits: i32 = L3_HUFFMAN_G_LINBITS[tab_num as usize] as i32;
-        if linbits != 0 {
-            loop {
-                let fresh5 = sfb;
-                sfb = sfb.offset(1);
-                np = *fresh5 as i32 / 2 as i32;
-                pairs_to_decode = if big_val_cnt > np { np } else { big_val_cnt };
-                let fresh6 = scf;
-                scf = scf.offset(1);
-                one = *fresh6;
-                loop {
-                    let mut j: i32 = 0;
-                    let mut w: i32 = 5 as i32;
-                    let mut leaf: i32 = *codebook
-                        .offset((bs_cache >> 32 as i32 - w) as isize)
-                        as i32;
-                    while leaf < 0 as i32 {
-                        bs_cache <<= w;
-                        bs_sh += w;
-                        w = leaf & 7 as i32;
-                        leaf = *codebook
-                            .offset(
-                                (bs_cache >> 32 as i32 - w)
-                                    .wrapping_sub((leaf >> 3 as i32) as u32)
-                                    as isize,
-                            ) as i32;
-                    }
-                    bs_cache <<= leaf >> 8 as i32;
-                    bs_sh += leaf >> 8 as i32;
-                    j = 0 as i32;
-                    while j < 2 as i32 {
-                        let mut lsb: i32 = leaf & 0xf as i32;
-                        if lsb == 15 as i32 {
-                            lsb = (lsb as u32)
-                                .wrapping_add(bs_cache >> 32 as i32 - linbits)
-                                as i32 as i32;
-                            bs_cache <<= linbits;
-                            bs_sh += linbits;
-                            while bs_sh >= 0 as i32 {
-                                let fresh7 = bs_next_ptr;
-                                bs_next_ptr = &bs_next_ptr[1..];
-                                bs_cache |= (fresh7[0] as u32) << bs_sh;
-                                bs_sh -= 8 as i32;
-                            }
-                            *dst = one * L3_pow_43(lsb)
-                                * (if (bs_cache as i32) < 0 as i32 {
-                                    -(1 as i32)
-                                } else {
-                                    1 as i32
-                                }) as f32;
-                        } else {
-                            *dst = G_POW43[((16 as i32 + lsb) as u32)
-                                .wrapping_sub(
-                                    16 as i32 as u32
-                                        * (bs_cache >> 31 as i32),
-                                ) as usize] * one;
-                        }
-                        bs_cache
-                            <<= if lsb != 0 {
-                                1 as i32
-                            } else {
-                                0 as i32
-                            };
-                        bs_sh
-                            += if lsb != 0 {
-                                1 as i32
-                            } else {
-                                0 as i32
-                            };
-                        j += 1;
-                        dst = dst.offset(1);
-                        leaf >>= 4 as i32;
-                    }
-                    while bs_sh >= 0 as i32 {
-                        let fresh8 = bs_next_ptr;
-                        bs_next_ptr = &bs_next_ptr[1..];
-                        bs_cache |= (fresh8[0] as u32) << bs_sh;
-                        bs_sh -= 8 as i32;
-                    }
-                    pairs_to_decode -= 1;
-                    if !(pairs_to_decode != 0) {
-                        break;
-                    }
-                }
-                big_val_cnt -= np;
-                if !(big_val_cnt > 0 as i32
-                    && {
-                        sfb_cnt -= 1;
-                        sfb_cnt >= 0 as i32
-                    })
-                {
-                    break;
-                }
-            }
-        } else {
-            loop {
-                let fresh9 = sfb;
-                sfb = sfb.offset(1);
-                np = *fresh9 as i32 / 2 as i32;
-                pairs_to_decode = if big_val_cnt > np { np } else { big_val_cnt };
-                let fresh10 = scf;
-                scf = scf.offset(1);
-                one = *fresh10;
-                loop {
-                    let mut j_0: i32 = 0;
-                    let mut w_0: i32 = 5 as i32;
-                    let mut leaf_0: i32 = *codebook
-                        .offset((bs_cache >> 32 as i32 - w_0) as isize)
-                        as i32;
-                    while leaf_0 < 0 as i32 {
-                        bs_cache <<= w_0;
-                        bs_sh += w_0;
-                        w_0 = leaf_0 & 7 as i32;
-                        leaf_0 = *codebook
-                            .offset(
-                                (bs_cache >> 32 as i32 - w_0)
-                                    .wrapping_sub((leaf_0 >> 3 as i32) as u32)
-                                    as isize,
-                            ) as i32;
-                    }
-                    bs_cache <<= leaf_0 >> 8 as i32;
-                    bs_sh += leaf_0 >> 8 as i32;
-                    j_0 = 0 as i32;
-                    while j_0 < 2 as i32 {
-                        let lsb_0: i32 = leaf_0 & 0xf as i32;
-                        *dst = G_POW43[((16 as i32 + lsb_0) as u32)
-                            .wrapping_sub(
-                                16 as i32 as u32
-                                    * (bs_cache >> 31 as i32),
-                            ) as usize] * one;
-                        bs_cache
-                            <<= if lsb_0 != 0 {
-                                1 as i32
-                            } else {
-                                0 as i32
-                            };
-                        bs_sh
-                            += if lsb_0 != 0 {
-                                1 as i32
-                            } else {
-                                0 as i32
-                            };
-                        j_0 += 1;
-                        dst = dst.offset(1);
-                        leaf_0 >>= 4 as i32;
-                    }
-                    while bs_sh >= 0 as i32 {
-                        let fresh11 = bs_next_ptr;
-                        bs_next_ptr = &bs_next_ptr[1..];
-                        bs_cache |= (fresh11[0] as u32) << bs_sh;
-                        bs_sh -= 8 as i32;
-                    }
-                    pairs_to_decode -= 1;
-                    if !(pairs_to_decode != 0) {
-                        break;
-                    }
-                }
-                big_val_cnt -= np;
-                if !(big_val_cnt > 0 as i32
-                    && {
-                        sfb_cnt -= 1;
-                        sfb_cnt >= 0 as i32
-                    })
-                {
-                    break;
-                }
+    let mut scf = scf.iter();
+    let mut sfb = gr_info.sfbtab.iter();
+    let start = (bs.pos / 8) as usize;
+    // The next bits are kept at the top of `bs_cache`, which is topped up a byte at a time from `bs_next`
+    let mut bs_cache = u32::from_be_bytes(bs.buf[start..start + 4].try_into().unwrap()) << (bs.pos & 7);
+    let mut bs_sh = (bs.pos & 7) - 8;
+    let mut bs_next = bs.buf[start + 4..].iter();
+    macro_rules! peek_bits {
+        ($n:expr) => {
+            bs_cache >> (32 - $n)
+        };
+    }
+    macro_rules! flush_bits {
+        ($n:expr) => {
+            bs_cache <<= $n;
+            bs_sh += $n;
+        };
+    }
+    macro_rules! check_bits {
+        () => {
+            while bs_sh >= 0 {
+                bs_cache |= (*bs_next.next().unwrap() as u32) << bs_sh;
+                bs_sh -= 8;
             }
-        }
+        };
     }
-    np = 1 as i32 - big_val_cnt;
-    loop {
-        let codebook_count1: *const u8 = if (*gr_info).count1_table
-            as i32 != 0
-        {
-            L3_HUFFMAN_TAB33.as_ptr()
-        } else {
-            L3_HUFFMAN_TAB32.as_ptr()
+    macro_rules! bs_pos {
+        () => {
+            (bs.buf.len() - bs_next.len()) as i32 * 8 - 24 + bs_sh
         };
-        let mut leaf_1: i32 = *codebook_count1
-            .offset((bs_cache >> 32 as i32 - 4 as i32) as isize)
-            as i32;
-        if leaf_1 & 8 as i32 == 0 {
-            leaf_1 = *codebook_count1
-                .offset(
-                    ((leaf_1 >> 3 as i32) as u32)
-                        .wrapping_add(
-                            bs_cache << 4 as i32
-                                >> 32 as i32 - (leaf_1 & 3 as i32),
-                        ) as isize,
-                ) as i32;
-        }
-        bs_cache <<= leaf_1 & 7 as i32;
-        bs_sh += leaf_1 & 7 as i32;
-        if (bs_next_ptr.as_ptr() as usize - bs.buf.as_ptr() as usize) as isize
-            * 8 as i32 as isize - 24 as i32 as isize
-            + bs_sh as isize > layer3gr_limit as isize
-        {
+    }
+
+    let mut idst = 0;
+    let mut one = const { gain(0.) };
+    let mut np = 0;
+    let mut big_val_cnt = gr_info.big_values as i32;
+    for (&tab_num, &region_count) in iter::zip(&gr_info.table_select, &gr_info.region_count) {
+        if big_val_cnt <= 0 {
             break;
         }
-        np -= 1;
-        if np == 0 {
-            let fresh12 = sfb;
-            sfb = sfb.offset(1);
-            np = *fresh12 as i32 / 2 as i32;
-            if np == 0 {
+        let codebook = &L3_HUFFMAN_TABS[L3_HUFFMAN_TABINDEX[tab_num as usize] as usize..];
+        let linbits = L3_HUFFMAN_G_LINBITS[tab_num as usize] as i32;
+        let mut sfb_cnt = region_count as i32;
+        loop {
+            np = *sfb.next().unwrap() as i32 / 2;
+            one = *scf.next().unwrap();
+            for _ in 0..big_val_cnt.min(np) {
+                let mut w = 5;
+                let mut leaf = codebook[peek_bits!(w) as usize] as i32;
+                while leaf < 0 {
+                    flush_bits!(w);
+                    w = leaf & 7;
+                    leaf = codebook[(peek_bits!(w) as i32 - (leaf >> 3)) as usize] as i32;
+                }
+                flush_bits!(leaf >> 8);
+                for _ in 0..2 {
+                    let mut lsb = leaf & 0xf;
+                    if linbits != 0 && lsb == 15 {
+                        lsb += peek_bits!(linbits) as i32;
+                        flush_bits!(linbits);
+                        check_bits!();
+                        let p = L3_pow_43(lsb);
+                        dst[idst] = L3_dequant(one, if (bs_cache as i32) < 0 { -p } else { p });
+                    } else {
+                        dst[idst] = L3_dequant(one, G_POW43[(16 + lsb - 16 * (bs_cache >> 31) as i32) as usize]);
+                    }
+                    flush_bits!((lsb != 0) as i32);
+                    idst += 1;
+                    leaf >>= 4;
+                }
+                check_bits!();
+            }
+            big_val_cnt -= np;
+            sfb_cnt -= 1;
+            if big_val_cnt <= 0 || sfb_cnt < 0 {
                 break;
             }
-            let fresh13 = scf;
-            scf = scf.offset(1);
-            one = *fresh13;
         }
-        if leaf_1 & 128 as i32 >> 0 as i32 != 0 {
-            *dst
-                .offset(
-                    0 as i32 as isize,
-                ) = if (bs_cache as i32) < 0 as i32 { -one } else { one };
-            bs_cache <<= 1 as i32;
-            bs_sh += 1 as i32;
+    }
+
+    let codebook_count1: &[u8] = if gr_info.count1_table != 0 { &L3_HUFFMAN_TAB33 } else { &L3_HUFFMAN_TAB32 };
+    let mut one = L3_dequant(one, G_POW43[17]);
+    np = 1 - big_val_cnt;
+    'quads: loop {
+        let mut leaf = codebook_count1[peek_bits!(4) as usize] as i32;
+        if leaf & 8 == 0 {
+            leaf = codebook_count1[((leaf >> 3) as u32 + (bs_cache << 4 >> (32 - (leaf & 3)))) as usize] as i32;
         }
-        if leaf_1 & 128 as i32 >> 1 as i32 != 0 {
-            *dst
-                .offset(
-                    1 as i32 as isize,
-                ) = if (bs_cache as i32) < 0 as i32 { -one } else { one };
-            bs_cache <<= 1 as i32;
-            bs_sh += 1 as i32;
+        flush_bits!(leaf & 7);
+        if bs_pos!() > layer3gr_limit {
+            break;
         }
-        np -= 1;
-        if np == 0 {
-            let fresh14 = sfb;
-            sfb = sfb.offset(1);
-            np = *fresh14 as i32 / 2 as i32;
+        for pair in [0, 2] {
+            np -= 1;
             if np == 0 {
-                break;
+                np = *sfb.next().unwrap() as i32 / 2;
+                if np == 0 {
+                    break 'quads;
+                }
+                one = L3_dequant(*scf.next().unwrap(), G_POW43[17]);
+            }
+            for i in pair..pair + 2 {
+                if leaf & 128 >> i != 0 {
+                    dst[idst + i] = if (bs_cache as i32) < 0 { -one } else { one };
+                    flush_bits!(1);
+                }
             }
-            let fresh15 = scf;
-            scf = scf.offset(1);
-            one = *fresh15;
-        }
-        if leaf_1 & 128 as i32 >> 2 as i32 != 0 {
-            *dst
-                .offset(
-                    2 as i32 as isize,
-                ) = if (bs_cache as i32) < 0 as i32 { -one } else { one };
-            bs_cache <<= 1 as i32;
-            bs_sh += 1 as i32;
-        }
-        if leaf_1 & 128 as i32 >> 3 as i32 != 0 {
-            *dst
-                .offset(
-                    3 as i32 as isize,
-                ) = if (bs_cache as i32) < 0 as i32 { -one } else { one };
-            bs_cache <<= 1 as i32;
-            bs_sh += 1 as i32;
-        }
-        while bs_sh >= 0 as i32 {
-            let fresh16 = bs_next_ptr;
-            bs_next_ptr = &bs_next_ptr[1..];
-            bs_cache |= (fresh16[0] as u32) << bs_sh;
-            bs_sh -= 8 as i32;
         }
-        dst = dst.offset(4 as i32 as isize);
+        check_bits!();
+        idst += 4;
     }
     bs.pos = layer3gr_limit;
 }
 
 fn L3_midside_stereo(
-    left: &mut [f32],
+    left: &mut [mp3d_real_t],
     n: usize,
 ) {
     let (left, right) = left.split_at_mut(576);
-    for (l, r) in iter::zip(left, right).take(n as usize) {
+    for (l, r) in iter::zip(left, right).take(n) {
         let a = *l;
         let b = *r;
         *l = a + b;
@@ -849,273 +756,151 @@ fn L3_midside_stereo(
 }
 
 fn L3_intensity_stereo_band(
-    left: &mut [f32],
+    left: &mut [mp3d_real_t],
     n: usize,
-    kl: f32,
-    kr: f32,
+    kl: mp3d_real_t,
+    kr: mp3d_real_t,
 ) {
-    for i in 0..n {
-        left[i + 576] = left[i] * kr;
-        left[i] = left[i] * kl;
+    let (left, right) = left.split_at_mut(576);
+    for (l, r) in iter::zip(left, right).take(n) {
+        *r = *l * kr;
+        *l = *l * kl;
     }
 }
 
-unsafe fn L3_stereo_top_band(
-    mut right: &[f32],
-    sfb: *const u8,
-    nbands: i32,
-    max_band: *mut i32,
+fn L3_stereo_top_band(
+    right: &[mp3d_real_t],
+    sfb: &[u8],
+    nbands: usize,
+    max_band: &mut [i32; 3],
 ) {
-    let mut i: i32 = 0;
-    let mut k = 0usize;
-    let ref mut fresh17 = *max_band.offset(2 as i32 as isize);
-    *fresh17 = -(1 as i32);
-    let ref mut fresh18 = *max_band.offset(1 as i32 as isize);
-    *fresh18 = *fresh17;
-    *max_band.offset(0 as i32 as isize) = *fresh18;
-    i = 0 as i32;
-    while i < nbands {
-        k = 0;
-        while k < *sfb.offset(i as isize) as usize {
-            if right[k] != 0 as i32 as f32
-                || right[k + 1]
-                    != 0 as i32 as f32
-            {
-                *max_band.offset((i % 3 as i32) as isize) = i;
-                break;
-            } else {
-                k += 2;
-            }
+    *max_band = [-1; 3];
+    let mut start = 0;
+    for (i, &width) in sfb[..nbands].iter().enumerate() {
+        let end = start + width as usize;
+        if right[start..end].iter().any(|&x| x != const { real(0.) }) {
+            max_band[i % 3] = i as i32;
         }
-        right = &right[*sfb.offset(i as isize) as usize..];
-        i += 1;
+        start = end;
     }
 }
-unsafe fn L3_stereo_process(
-    mut left: &mut [f32],
-    ist_pos: *const u8,
-    sfb: *const u8,
+
+fn L3_stereo_process(
+    mut left: &mut [mp3d_real_t],
+    ist_pos: &[u8],
+    sfb: &[u8],
     hdr: &[u8],
-    max_band: *mut i32,
+    max_band: &[i32; 3],
     mpeg2_sh: i32,
 ) {
-    let mut i: u32 = 0;
-    let max_pos: u32 = if hdr[1] & 0x8 != 0 {
-        7
-    } else {
-        64
-    };
-    i = 0 as i32 as u32;
-    while *sfb.offset(i as isize) != 0 {
-        let ipos: u32 = *ist_pos.offset(i as isize) as u32;
-        if i as i32
-            > *max_band.offset(i.wrapping_rem(3 as i32 as u32) as isize)
-            && ipos < max_pos
-        {
-            let mut kl: f32 = 0.;
-            let mut kr: f32 = 0.;
-            let s: f32 = if hdr[3] & 0x20 != 0 {
-                1.41421356f32
-            } else {
-                1f32
-            };
-            if hdr[1] & 0x8 != 0 {
-                kl = L3_STEREO_PROCESS_G_PAN[(2 as i32 as u32).wrapping_mul(ipos)
-                    as usize];
-                kr = L3_STEREO_PROCESS_G_PAN[(2 as i32 as u32)
-                    .wrapping_mul(ipos)
-                    .wrapping_add(1 as i32 as u32) as usize];
+    let max_pos = if hdr[1] & 0x8 != 0 { 7 } else { 64 };
+    for (i, &width) in sfb.iter().take_while(|&&width| width != 0).enumerate() {
+        let width = width as usize;
+        let ipos = ist_pos[i] as usize;
+        if i as i32 > max_band[i % 3] && ipos < max_pos {
+            let s = if hdr[3] & 0x20 != 0 { const { real(1.41421356) } } else { const { real(1.) } };
+            let (kl, kr) = if hdr[1] & 0x8 != 0 {
+                (L3_STEREO_PROCESS_G_PAN[2 * ipos], L3_STEREO_PROCESS_G_PAN[2 * ipos + 1])
             } else {
-                kl = 1 as i32 as f32;
-                kr = L3_ldexp_q2(
-                    1 as i32 as f32,
-                    ((ipos.wrapping_add(1 as i32 as u32)
-                        >> 1 as i32) << mpeg2_sh) as i32,
-                );
-                if ipos & 1 as i32 as u32 != 0 {
-                    kl = kr;
-                    kr = 1 as i32 as f32;
+                let k = gain_real(L3_ldexp_q2(const { gain(1.) }, ((ipos + 1) >> 1 << mpeg2_sh) as i32));
+                if ipos & 1 != 0 {
+                    (k, const { real(1.) })
+                } else {
+                    (const { real(1.) }, k)
                 }
-            }
-            L3_intensity_stereo_band(
-                left,
-                *sfb.offset(i as isize) as usize,
-                kl * s,
-                kr * s,
-            );
+            };
+            L3_intensity_stereo_band(left, width, kl * s, kr * s);
         } else if hdr[3] & 0x20 != 0 {
-            L3_midside_stereo(left, *sfb.offset(i as isize) as usize);
+            L3_midside_stereo(left, width);
         }
-        left = &mut left[*sfb.offset(i as isize) as usize..];
-        i = i.wrapping_add(1);
+        left = &mut left[width..];
     }
 }
-unsafe fn L3_intensity_stereo(
-    left: &mut [f32],
-    ist_pos: *mut u8,
+
+fn L3_intensity_stereo(
+    left: &mut [mp3d_real_t],
+    ist_pos: &mut [u8],
     gr: &[L3_gr_info_t],
     hdr: &[u8],
 ) {
-    let mut max_band: [i32; 3] = [0; 3];
-    let n_sfb: i32 = gr[0].n_long_sfb as i32
-        + gr[0].n_short_sfb as i32;
-    let mut i: i32 = 0;
-    let max_blocks: i32 = if gr[0].n_short_sfb as i32 != 0 {
-        3 as i32
-    } else {
-        1 as i32
-    };
-    L3_stereo_top_band(
-        &left[576..],
-        gr[0].sfbtab,
-        n_sfb,
-        max_band.as_mut_ptr(),
-    );
+    let mut max_band = [0; 3];
+    let n_sfb = gr[0].n_long_sfb as usize + gr[0].n_short_sfb as usize;
+    let max_blocks = if gr[0].n_short_sfb != 0 { 3 } else { 1 };
+    L3_stereo_top_band(&left[576..], gr[0].sfbtab, n_sfb, &mut max_band);
     if gr[0].n_long_sfb != 0 {
-        max_band[2 as i32
-            as usize] = if (if max_band[0 as i32 as usize]
-            < max_band[1 as i32 as usize]
-        {
-            max_band[1 as i32 as usize]
-        } else {
-            max_band[0 as i32 as usize]
-        }) < max_band[2 as i32 as usize]
-        {
-            max_band[2 as i32 as usize]
-        } else if max_band[0 as i32 as usize]
-            < max_band[1 as i32 as usize]
-        {
-            max_band[1 as i32 as usize]
-        } else {
-            max_band[0 as i32 as usize]
-        };
-        max_band[1 as i32 as usize] = max_band[2 as i32 as usize];
-        max_band[0 as i32 as usize] = max_band[1 as i32 as usize];
+        max_band = [max_band.into_iter().max().unwrap(); 3];
     }
-    i = 0 as i32;
-    while i < max_blocks {
-        let default_pos: i32 = if hdr[1] & 0x8 != 0 {
-            3
-        } else {
-            0
-        };
-        let itop: i32 = n_sfb - max_blocks + i;
-        let prev: i32 = itop - max_blocks;
-        *ist_pos
-            .offset(
-                itop as isize,
-            ) = (if max_band[i as usize] >= prev {
-            default_pos
-        } else {
-            *ist_pos.offset(prev as isize) as i32
-        }) as u8;
-        i += 1;
+    let default_pos = if hdr[1] & 0x8 != 0 { 3 } else { 0 };
+    for i in 0..max_blocks {
+        let itop = n_sfb - max_blocks + i;
+        let prev = itop - max_blocks;
+        ist_pos[itop] = if max_band[i] >= prev as i32 { default_pos } else { ist_pos[prev] };
     }
-    L3_stereo_process(
-        left,
-        ist_pos,
-        gr[0].sfbtab,
-        hdr,
-        max_band.as_mut_ptr(),
-        gr[1].scalefac_compress as i32
-            & 1 as i32,
-    );
+    L3_stereo_process(left, ist_pos, gr[0].sfbtab, hdr, &max_band, (gr[1].scalefac_compress & 1) as i32);
 }
-unsafe fn L3_reorder(
-    grbuf: *mut f32,
-    scratch: *mut f32,
-    mut sfb: *const u8,
+
+fn L3_reorder(
+    grbuf: &mut [mp3d_real_t],
+    scratch: &mut [mp3d_real_t],
+    sfb: &[u8],
 ) {
-    let mut i: i32 = 0;
-    let mut len: i32 = 0;
-    let mut src: *mut f32 = grbuf;
-    let mut dst: *mut f32 = scratch;
-    loop {
-        len = *sfb as i32;
-        if !(0 as i32 != len) {
-            break;
-        }
-        i = 0 as i32;
-        while i < len {
-            let fresh19 = dst;
-            dst = dst.offset(1);
-            *fresh19 = *src.offset((0 as i32 * len) as isize);
-            let fresh20 = dst;
-            dst = dst.offset(1);
-            *fresh20 = *src.offset((1 as i32 * len) as isize);
-            let fresh21 = dst;
-            dst = dst.offset(1);
-            *fresh21 = *src.offset((2 as i32 * len) as isize);
-            i += 1;
-            src = src.offset(1);
+    // Interleave the three windows of each band, which share its width
+    let mut start = 0;
+    for &len in sfb.iter().step_by(3).take_while(|&&len| len != 0) {
+        let len = len as usize;
+        let end = start + 3 * len;
+        let band = &grbuf[start..end];
+        for (i, dst) in scratch[start..end].chunks_exact_mut(3).enumerate() {
+            dst.copy_from_slice(&[band[i], band[len + i], band[2 * len + i]]);
         }
-        sfb = sfb.offset(3 as i32 as isize);
-        src = src.offset((2 as i32 * len) as isize);
+        start = end;
     }
-    memcpy(
-        grbuf as *mut (),
-        scratch as *const (),
-        (dst.offset_from(scratch) as isize as usize)
-            .wrapping_mul(::core::mem::size_of::<f32>() as usize),
-    );
+    grbuf[..start].copy_from_slice(&scratch[..start]);
 }
+
 fn L3_antialias(
-    mut grbuf: &mut [f32],
-    nbands: i32,
+    grbuf: &mut [mp3d_real_t],
+    nbands: usize,
 ) {
-    for _ in 0..nbands {
-        let mut i: i32 = 0 as i32;
-        while i < 8 as i32 {
-            let u: f32 = grbuf[(18 as i32 + i) as usize];
-            let d: f32 = grbuf[(17 as i32 - i) as usize];
-            grbuf[(18 as i32 + i) as usize] = u * L3_ANTIALIAS_G_AA[0 as i32 as usize][i as usize]
-                - d * L3_ANTIALIAS_G_AA[1 as i32 as usize][i as usize];
-            grbuf[(17 as i32 - i) as usize] = u * L3_ANTIALIAS_G_AA[1 as i32 as usize][i as usize]
-                + d * L3_ANTIALIAS_G_AA[0 as i32 as usize][i as usize];
-            i += 1;
+    for band in 0..nbands {
+        // Butterflies across the boundary between this band and the next
+        let grbuf = &mut grbuf[18 * band..];
+        #[cfg(not(feature = "forbid-unsafe"))]
+        let start = if simd::ENABLED {
+            simd::antialias(grbuf);
+            8
+        } else {
+            0
+        };
+        #[cfg(feature = "forbid-unsafe")]
+        let start = 0;
+        for i in start..8 {
+            let u = grbuf[18 + i];
+            let d = grbuf[17 - i];
+            grbuf[18 + i] = u * L3_ANTIALIAS_G_AA[0][i] - d * L3_ANTIALIAS_G_AA[1][i];
+            grbuf[17 - i] = u * L3_ANTIALIAS_G_AA[1][i] + d * L3_ANTIALIAS_G_AA[0][i];
         }
-        grbuf = &mut grbuf[18..];
     }
 }
 
-fn L3_dct3_9(y: &mut [f32]) {
-    let mut s0: f32 = 0.;
-    let mut s1: f32 = 0.;
-    let mut s2: f32 = 0.;
-    let mut s3: f32 = 0.;
-    let mut s4: f32 = 0.;
-    let mut s5: f32 = 0.;
-    let mut s6: f32 = 0.;
-    let mut s7: f32 = 0.;
-    let mut s8: f32 = 0.;
-    let mut t0: f32 = 0.;
-    let mut t2: f32 = 0.;
-    let mut t4: f32 = 0.;
-    s0 = y[0];
-    s2 = y[2];
-    s4 = y[4];
-    s6 = y[6];
-    s8 = y[8];
-    t0 = s0 + s6 * 0.5f32;
+fn L3_dct3_9(y: &mut [mp3d_real_t; 9]) {
+    let [mut s0, mut s1, mut s2, mut s3, mut s4, mut s5, mut s6, mut s7, mut s8] = *y;
+    let mut t0 = s0 + s6 * const { real(0.5) };
     s0 -= s6;
-    t4 = (s4 + s2) * 0.93969262f32;
-    t2 = (s8 + s2) * 0.76604444f32;
-    s6 = (s4 - s8) * 0.17364818f32;
+    let mut t4 = (s4 + s2) * const { real(0.93969262) };
+    let mut t2 = (s8 + s2) * const { real(0.76604444) };
+    s6 = (s4 - s8) * const { real(0.17364818) };
     s4 += s8 - s2;
-    s2 = s0 - s4 * 0.5f32;
+    s2 = s0 - s4 * const { real(0.5) };
     y[4] = s4 + s0;
     s8 = t0 - t2 + s6;
     s0 = t0 - t4 + t2;
     s4 = t0 + t4 - s6;
-    s1 = y[1];
-    s3 = y[3];
-    s5 = y[5];
-    s7 = y[7];
-    s3 *= 0.86602540f32;
-    t0 = (s5 + s1) * 0.98480775f32;
-    t4 = (s5 - s7) * 0.34202014f32;
-    t2 = (s1 + s7) * 0.64278761f32;
-    s1 = (s1 - s5 - s7) * 0.86602540f32;
+    s3 *= const { real(0.86602540) };
+    t0 = (s5 + s1) * const { real(0.98480775) };
+    t4 = (s5 - s7) * const { real(0.34202014) };
+    t2 = (s1 + s7) * const { real(0.64278761) };
+    s1 = (s1 - s5 - s7) * const { real(0.86602540) };
     s5 = t0 - s3 - t2;
     s7 = t4 - s3 - t0;
     s3 = t4 + s3 - t2;
@@ -1129,928 +914,394 @@ fn L3_dct3_9(y: &mut [f32]) {
     y[8] = s4 + s7;
 }
 
-unsafe fn L3_imdct36(
-    mut grbuf: *mut f32,
-    mut overlap: *mut f32,
-    window: *const f32,
-    nbands: i32,
+fn L3_imdct36(
+    grbuf: &mut [mp3d_real_t],
+    overlap: &mut [mp3d_real_t],
+    window: &[mp3d_real_t; 18],
 ) {
-    let mut i: i32 = 0;
-    let mut j: i32 = 0;
-     
-    j = 0 as i32;
-    while j < nbands {
-        let mut co: [f32; 9] = [0.; 9];
-        let mut si: [f32; 9] = [0.; 9];
-        co[0 as i32 as usize] = -*grbuf.offset(0 as i32 as isize);
-        si[0 as i32 as usize] = *grbuf.offset(17 as i32 as isize);
-        i = 0 as i32;
-        while i < 4 as i32 {
-            si[(8 as i32 - 2 as i32 * i)
-                as usize] = *grbuf
-                .offset((4 as i32 * i + 1 as i32) as isize)
-                - *grbuf.offset((4 as i32 * i + 2 as i32) as isize);
-            co[(1 as i32 + 2 as i32 * i)
-                as usize] = *grbuf
-                .offset((4 as i32 * i + 1 as i32) as isize)
-                + *grbuf.offset((4 as i32 * i + 2 as i32) as isize);
-            si[(7 as i32 - 2 as i32 * i)
-                as usize] = *grbuf
-                .offset((4 as i32 * i + 4 as i32) as isize)
-                - *grbuf.offset((4 as i32 * i + 3 as i32) as isize);
-            co[(2 as i32 + 2 as i32 * i)
-                as usize] = -(*grbuf
-                .offset((4 as i32 * i + 3 as i32) as isize)
-                + *grbuf.offset((4 as i32 * i + 4 as i32) as isize));
-            i += 1;
+    for (grbuf, overlap) in iter::zip(grbuf.chunks_exact_mut(18), overlap.chunks_exact_mut(9)) {
+        let mut co = [const { real(0.) }; 9];
+        let mut si = [const { real(0.) }; 9];
+        co[0] = -grbuf[0];
+        si[0] = grbuf[17];
+        for i in 0..4 {
+            si[8 - 2 * i] = grbuf[4 * i + 1] - grbuf[4 * i + 2];
+            co[1 + 2 * i] = grbuf[4 * i + 1] + grbuf[4 * i + 2];
+            si[7 - 2 * i] = grbuf[4 * i + 4] - grbuf[4 * i + 3];
+            co[2 + 2 * i] = -(grbuf[4 * i + 3] + grbuf[4 * i + 4]);
         }
         L3_dct3_9(&mut co);
         L3_dct3_9(&mut si);
-        si[1 as i32 as usize] = -si[1 as i32 as usize];
-        si[3 as i32 as usize] = -si[3 as i32 as usize];
-        si[5 as i32 as usize] = -si[5 as i32 as usize];
-        si[7 as i32 as usize] = -si[7 as i32 as usize];
-        i = 0 as i32;
-        while i < 9 as i32 {
-            let ovl: f32 = *overlap.offset(i as isize);
-            let sum: f32 = co[i as usize]
-                * L3_IMDCT36_G_TWID9[(9 as i32 + i) as usize]
-                + si[i as usize] * L3_IMDCT36_G_TWID9[(0 as i32 + i) as usize];
-            *overlap
-                .offset(
-                    i as isize,
-                ) = co[i as usize] * L3_IMDCT36_G_TWID9[(0 as i32 + i) as usize]
-                - si[i as usize] * L3_IMDCT36_G_TWID9[(9 as i32 + i) as usize];
-            *grbuf
-                .offset(
-                    i as isize,
-                ) = ovl * *window.offset((0 as i32 + i) as isize)
-                - sum * *window.offset((9 as i32 + i) as isize);
-            *grbuf
-                .offset(
-                    (17 as i32 - i) as isize,
-                ) = ovl * *window.offset((9 as i32 + i) as isize)
-                + sum * *window.offset((0 as i32 + i) as isize);
-            i += 1;
+        for i in [1, 3, 5, 7] {
+            si[i] = -si[i];
+        }
+        #[cfg(not(feature = "forbid-unsafe"))]
+        let start = if simd::ENABLED {
+            simd::imdct36_window(grbuf, overlap, &co, &si, window);
+            8
+        } else {
+            0
+        };
+        #[cfg(feature = "forbid-unsafe")]
+        let start = 0;
+        for i in start..9 {
+            let ovl = overlap[i];
+            let sum = co[i] * L3_IMDCT36_G_TWID9[9 + i] + si[i] * L3_IMDCT36_G_TWID9[i];
+            overlap[i] = co[i] * L3_IMDCT36_G_TWID9[i] - si[i] * L3_IMDCT36_G_TWID9[9 + i];
+            grbuf[i] = ovl * window[i] - sum * window[9 + i];
+            grbuf[17 - i] = ovl * window[9 + i] + sum * window[i];
         }
-        j += 1;
-        grbuf = grbuf.offset(18 as i32 as isize);
-        overlap = overlap.offset(9 as i32 as isize);
     }
 }
+
 fn L3_idct3(
-    x0: f32,
-    x1: f32,
-    x2: f32,
-    dst: &mut [f32],
-) {
-    let m1: f32 = x1 * 0.86602540f32;
-    let a1: f32 = x0 - x2 * 0.5f32;
-    dst[1] = x0 + x2;
-    dst[0] = a1 + m1;
-    dst[2] = a1 - m1;
+    x0: mp3d_real_t,
+    x1: mp3d_real_t,
+    x2: mp3d_real_t,
+) -> [mp3d_real_t; 3] {
+    let m1 = x1 * const { real(0.86602540) };
+    let a1 = x0 - x2 * const { real(0.5) };
+    [a1 + m1, x0 + x2, a1 - m1]
 }
-unsafe fn L3_imdct12(
-    x: *mut f32,
-    dst: *mut f32,
-    overlap: *mut f32,
+
+fn L3_imdct12(
+    x: &[mp3d_real_t],
+    dst: &mut [mp3d_real_t],
+    overlap: &mut [mp3d_real_t],
 ) {
-    let mut co: [f32; 3] = [0.; 3];
-    let mut si: [f32; 3] = [0.; 3];
-    let mut i: i32 = 0;
-    L3_idct3(
-        -*x.offset(0 as i32 as isize),
-        *x.offset(6 as i32 as isize) + *x.offset(3 as i32 as isize),
-        *x.offset(12 as i32 as isize) + *x.offset(9 as i32 as isize),
-        &mut co,
-    );
-    L3_idct3(
-        *x.offset(15 as i32 as isize),
-        *x.offset(12 as i32 as isize) - *x.offset(9 as i32 as isize),
-        *x.offset(6 as i32 as isize) - *x.offset(3 as i32 as isize),
-        &mut si,
-    );
-    si[1 as i32 as usize] = -si[1 as i32 as usize];
-    i = 0 as i32;
-    while i < 3 as i32 {
-        let ovl: f32 = *overlap.offset(i as isize);
-        let sum: f32 = co[i as usize]
-            * L3_IMDCT12_G_TWID3[(3 as i32 + i) as usize]
-            + si[i as usize] * L3_IMDCT12_G_TWID3[(0 as i32 + i) as usize];
-        *overlap
-            .offset(
-                i as isize,
-            ) = co[i as usize] * L3_IMDCT12_G_TWID3[(0 as i32 + i) as usize]
-            - si[i as usize] * L3_IMDCT12_G_TWID3[(3 as i32 + i) as usize];
-        *dst
-            .offset(
-                i as isize,
-            ) = ovl * L3_IMDCT12_G_TWID3[(2 as i32 - i) as usize]
-            - sum * L3_IMDCT12_G_TWID3[(5 as i32 - i) as usize];
-        *dst
-            .offset(
-                (5 as i32 - i) as isize,
-            ) = ovl * L3_IMDCT12_G_TWID3[(5 as i32 - i) as usize]
-            + sum * L3_IMDCT12_G_TWID3[(2 as i32 - i) as usize];
-        i += 1;
+    let co = L3_idct3(-x[0], x[6] + x[3], x[12] + x[9]);
+    let mut si = L3_idct3(x[15], x[12] - x[9], x[6] - x[3]);
+    si[1] = -si[1];
+    for i in 0..3 {
+        let ovl = overlap[i];
+        let sum = co[i] * L3_IMDCT12_G_TWID3[3 + i] + si[i] * L3_IMDCT12_G_TWID3[i];
+        overlap[i] = co[i] * L3_IMDCT12_G_TWID3[i] - si[i] * L3_IMDCT12_G_TWID3[3 + i];
+        dst[i] = ovl * L3_IMDCT12_G_TWID3[2 - i] - sum * L3_IMDCT12_G_TWID3[5 - i];
+        dst[5 - i] = ovl * L3_IMDCT12_G_TWID3[5 - i] + sum * L3_IMDCT12_G_TWID3[2 - i];
     }
 }
-unsafe fn L3_imdct_short(
-    mut grbuf: *mut f32,
-    mut overlap: *mut f32,
-    mut nbands: i32,
+
+fn L3_imdct_short(
+    grbuf: &mut [mp3d_real_t],
+    overlap: &mut [mp3d_real_t],
 ) {
-    while nbands > 0 as i32 {
-        let mut tmp: [f32; 18] = [0.; 18];
-        memcpy(
-            tmp.as_mut_ptr() as *mut (),
-            grbuf as *const (),
-            ::core::mem::size_of::<[f32; 18]>() as usize,
-        );
-        memcpy(
-            grbuf as *mut (),
-            overlap as *const (),
-            (6 as i32 as usize)
-                .wrapping_mul(::core::mem::size_of::<f32>() as usize),
-        );
-        L3_imdct12(
-            tmp.as_mut_ptr(),
-            grbuf.offset(6 as i32 as isize),
-            overlap.offset(6 as i32 as isize),
-        );
-        L3_imdct12(
-            tmp.as_mut_ptr().offset(1 as i32 as isize),
-            grbuf.offset(12 as i32 as isize),
-            overlap.offset(6 as i32 as isize),
-        );
-        L3_imdct12(
-            tmp.as_mut_ptr().offset(2 as i32 as isize),
-            overlap,
-            overlap.offset(6 as i32 as isize),
-        );
-        nbands -= 1;
-        overlap = overlap.offset(9 as i32 as isize);
-        grbuf = grbuf.offset(18 as i32 as isize);
+    for (grbuf, overlap) in iter::zip(grbuf.chunks_exact_mut(18), overlap.chunks_exact_mut(9)) {
+        let tmp: [mp3d_real_t; 18] = (*grbuf).try_into().unwrap();
+        grbuf[..6].copy_from_slice(&overlap[..6]);
+        let (ovl, ovl6) = overlap.split_at_mut(6);
+        L3_imdct12(&tmp, &mut grbuf[6..], ovl6);
+        L3_imdct12(&tmp[1..], &mut grbuf[12..], ovl6);
+        L3_imdct12(&tmp[2..], ovl, ovl6);
     }
 }
-fn L3_change_sign(mut grbuf: &mut [f32]) {
-    let mut b = 0u32;
-    let mut i = 0usize;
-    grbuf = &mut grbuf[18..];
-    while b < 32 {
-        i = 1;
-        while i < 18 {
-            grbuf[i] = -grbuf[i];
-            i += 2;
+
+fn L3_change_sign(grbuf: &mut [mp3d_real_t]) {
+    for band in grbuf.chunks_exact_mut(18).skip(1).step_by(2) {
+        for x in band.iter_mut().skip(1).step_by(2) {
+            *x = -*x;
         }
-        b += 2;
-        if b < 32 { grbuf = &mut grbuf[36..]; }
     }
 }
-unsafe fn L3_imdct_gr(
-    mut grbuf: *mut f32,
-    mut overlap: *mut f32,
-    block_type: u32,
-    n_long_bands: u32,
+
+fn L3_imdct_gr(
+    grbuf: &mut [mp3d_real_t],
+    overlap: &mut [mp3d_real_t],
+    block_type: u8,
+    n_long_bands: usize,
 ) {
-    if n_long_bands != 0 {
-        L3_imdct36(
-            grbuf,
-            overlap,
-            (L3_IMDCT_GR_G_MDCT_WINDOW[0 as i32 as usize]).as_ptr(),
-            n_long_bands as i32,
-        );
-        grbuf = grbuf
-            .offset(
-                (18 as i32 as u32).wrapping_mul(n_long_bands) as isize,
-            );
-        overlap = overlap
-            .offset(
-                (9 as i32 as u32).wrapping_mul(n_long_bands) as isize,
-            );
-    }
-    if block_type == 2 as i32 as u32 {
-        L3_imdct_short(
-            grbuf,
-            overlap,
-            (32 as i32 as u32).wrapping_sub(n_long_bands) as i32,
-        );
+    let (long_grbuf, grbuf) = grbuf.split_at_mut(18 * n_long_bands);
+    let (long_overlap, overlap) = overlap.split_at_mut(9 * n_long_bands);
+    L3_imdct36(long_grbuf, long_overlap, &L3_IMDCT_GR_G_MDCT_WINDOW[0]);
+    if block_type == 2 {
+        L3_imdct_short(grbuf, overlap);
     } else {
-        L3_imdct36(
-            grbuf,
-            overlap,
-            (L3_IMDCT_GR_G_MDCT_WINDOW[(block_type == 3 as i32 as u32)
-                as i32 as usize])
-                .as_ptr(),
-            (32 as i32 as u32).wrapping_sub(n_long_bands) as i32,
-        );
-    };
+        L3_imdct36(grbuf, overlap, &L3_IMDCT_GR_G_MDCT_WINDOW[(block_type == 3) as usize]);
+    }
 }
 
 fn L3_save_reservoir(
     h: &mut mp3dec_t,
-    s_bs: &mut bs_t
+    s_bs: &bs_t
 ) {
-    let mut pos: i32 = ((s_bs.pos + 7 as i32) as u32)
-        .wrapping_div(8 as u32) as i32;
-    let mut remains: i32 = (s_bs.limit as u32)
-        .wrapping_div(8 as u32)
-        .wrapping_sub(pos as u32) as i32;
-    if remains > 511 as i32 {
-        pos += remains - 511 as i32;
-        remains = 511 as i32;
+    let mut pos = (s_bs.pos + 7) / 8;
+    let mut remains = s_bs.limit / 8 - pos;
+    if remains > 511 {
+        pos += remains - 511;
+        remains = 511;
     }
-    if remains > 0 as i32 {
-        h.reserv_buf[..remains as usize].copy_from_slice(&s_bs.buf[pos as usize..(pos+remains) as usize]);
+    if remains > 0 {
+        h.reserv_buf[..remains as usize].copy_from_slice(&s_bs.buf[pos as usize..][..remains as usize]);
     }
-    (*h).reserv = remains;
+    h.reserv = remains;
 }
 
 fn L3_restore_reservoir<'a>(
     h: &mut mp3dec_t,
-    bs: &mut bs_t,
+    bs: &bs_t,
     s_maindata: &'a mut [u8; 2815],
     s_bs: &mut bs_t<'a>,
     main_data_begin: i32,
-) -> i32 {
-    let frame_bytes: i32 = (bs.limit - bs.pos) / 8 as i32;
-    let bytes_have: i32 = if (*h).reserv > main_data_begin {
-        main_data_begin
-    } else {
-        (*h).reserv
-    };
-
-    {
-        let off = if (0 as i32) < (*h).reserv - main_data_begin {
-            (*h).reserv - main_data_begin
-        } else {
-            0 as i32
-        };
-        let cnt = if (*h).reserv > main_data_begin { main_data_begin } else { (*h).reserv };
-        s_maindata[..cnt as usize].copy_from_slice(&h.reserv_buf[off as usize..off as usize + cnt as usize]);
-    }
-
-    s_maindata[bytes_have as usize..bytes_have as usize + frame_bytes as usize]
-        .copy_from_slice(&bs.buf[(bs.pos / 8) as usize..(bs.pos / 8) as usize + frame_bytes as usize]);
-
-    *s_bs = bs_init(&s_maindata[..], bytes_have + frame_bytes);
-    return ((*h).reserv >= main_data_begin) as i32;
+) -> bool {
+    let frame_bytes = ((bs.limit - bs.pos) / 8) as usize;
+    let bytes_have = h.reserv.min(main_data_begin) as usize;
+    let off = (h.reserv - main_data_begin).max(0) as usize;
+    s_maindata[..bytes_have].copy_from_slice(&h.reserv_buf[off..off + bytes_have]);
+    let start = (bs.pos / 8) as usize;
+    s_maindata[bytes_have..bytes_have + frame_bytes].copy_from_slice(&bs.buf[start..start + frame_bytes]);
+    *s_bs = bs_init(&s_maindata[..], (bytes_have + frame_bytes) as i32);
+    h.reserv >= main_data_begin
 }
-unsafe fn L3_decode(
+
+fn L3_decode(
     h: &mut mp3dec_t,
     s: &mut mp3dec_scratch_t,
     s_bs: &mut bs_t,
-    mut gr_info: &mut [L3_gr_info_t],
+    gr_info: &[L3_gr_info_t],
     nch: u32,
 ) {
-    let mut ch: u32 = 0;
-    while ch < nch {
-        let layer3gr_limit: i32 = s_bs.pos
-            + gr_info[ch as usize].part_23_length as i32;
-        L3_decode_scalefactors(
-            &(*h).header,
-            ((*s).ist_pos[ch as usize]).as_mut_ptr(),
-            s_bs,
-            &gr_info[ch as usize],
-            ((*s).scf).as_mut_ptr(),
-            ch,
-        );
-        L3_huffman(
-            ((*s).grbuf[ch as usize]).as_mut_ptr(),
-            s_bs,
-            &gr_info[ch as usize],
-            ((*s).scf).as_mut_ptr(),
-            layer3gr_limit,
-        );
-        ch += 1;
+    for (ch, gr) in gr_info[..nch as usize].iter().enumerate() {
+        let layer3gr_limit = s_bs.pos + gr.part_23_length as i32;
+        L3_decode_scalefactors(&h.header, &mut s.ist_pos[ch], s_bs, gr, &mut s.scf, ch as u32);
+        L3_huffman(&mut s.grbuf[ch], s_bs, gr, &s.scf, layer3gr_limit);
     }
-    if (*h).header[3 as i32 as usize] as i32 & 0x10 as i32 != 0 {
-        L3_intensity_stereo(
-            (*s).grbuf.as_flattened_mut(),
-            ((*s).ist_pos[1 as i32 as usize]).as_mut_ptr(),
-            gr_info,
-            &(*h).header,
-        );
-    } else if (*h).header[3 as i32 as usize] as i32 & 0xe0 as i32
-        == 0x60 as i32
-    {
-        L3_midside_stereo(
-            (*s).grbuf.as_flattened_mut(),
-            576,
-        );
+    if h.header[3] & 0x10 != 0 {
+        L3_intensity_stereo(s.grbuf.as_flattened_mut(), &mut s.ist_pos[1], gr_info, &h.header);
+    } else if h.header[3] & 0xe0 == 0x60 {
+        L3_midside_stereo(s.grbuf.as_flattened_mut(), 576);
     }
-    ch = 0;
-    while ch < nch {
-        let mut aa_bands: i32 = 31 as i32;
-        let n_long_bands: i32 = (if gr_info[0].mixed_block_flag
-            as i32 != 0
-        {
-            2 as i32
-        } else {
-            0 as i32
-        })
-            << (((*h).header[2 as i32 as usize] as i32
-                >> 2 as i32 & 3 as i32)
-                + (((*h).header[1 as i32 as usize] as i32
-                    >> 3 as i32 & 1 as i32)
-                    + ((*h).header[1 as i32 as usize] as i32
-                        >> 4 as i32 & 1 as i32)) * 3 as i32
-                == 2 as i32) as i32;
-        if gr_info[0].n_short_sfb != 0 {
-            aa_bands = n_long_bands - 1 as i32;
+    for (ch, gr) in gr_info[..nch as usize].iter().enumerate() {
+        let mut aa_bands = 31;
+        let n_long_bands: usize = (if gr.mixed_block_flag != 0 { 2 } else { 0 }) << (hdr_my_sample_rate(&h.header) == 2) as u32;
+        if gr.n_short_sfb != 0 {
+            aa_bands = n_long_bands.saturating_sub(1);
             L3_reorder(
-                ((*s).grbuf[ch as usize])
-                    .as_mut_ptr()
-                    .offset((n_long_bands * 18 as i32) as isize),
-                (*s).syn.as_flattened_mut().as_mut_ptr(),
-                (gr_info[0].sfbtab).offset(gr_info[0].n_long_sfb as i32 as isize),
+                &mut s.grbuf[ch][18 * n_long_bands..],
+                s.syn.as_flattened_mut(),
+                &gr.sfbtab[gr.n_long_sfb as usize..],
             );
         }
-        L3_antialias(&mut s.grbuf[ch as usize], aa_bands);
-        L3_imdct_gr(
-            ((*s).grbuf[ch as usize]).as_mut_ptr(),
-            ((*h).mdct_overlap[ch as usize]).as_mut_ptr(),
-            gr_info[0].block_type as u32,
-            n_long_bands as u32,
-        );
-        L3_change_sign(&mut (*s).grbuf[ch as usize]);
-        ch += 1;
-        gr_info = &mut gr_info[1..];
+        L3_antialias(&mut s.grbuf[ch], aa_bands);
+        L3_imdct_gr(&mut s.grbuf[ch], &mut h.mdct_overlap[ch], gr.block_type, n_long_bands);
+        L3_change_sign(&mut s.grbuf[ch]);
     }
 }
-unsafe fn mp3d_DCT_II(grbuf: *mut f32, n: u32) {
-    let mut i: i32 = 0;
-    let mut k = 0;
-    while k < n {
-        let mut t: [[f32; 8]; 4] = [[0.; 8]; 4];
-        let mut x: *mut f32 = t.as_flattened_mut().as_mut_ptr();
-        let mut y: *mut f32 = grbuf.offset(k as isize);
-        i = 0 as i32;
-        while i < 8 as i32 {
-            let x0: f32 = *y.offset((i * 18 as i32) as isize);
-            let x1: f32 = *y
-                .offset(((15 as i32 - i) * 18 as i32) as isize);
-            let x2: f32 = *y
-                .offset(((16 as i32 + i) * 18 as i32) as isize);
-            let x3: f32 = *y
-                .offset(((31 as i32 - i) * 18 as i32) as isize);
-            let t0: f32 = x0 + x3;
-            let t1: f32 = x1 + x2;
-            let t2: f32 = (x1 - x2)
-                * MP3D_DCT_II_G_SEC[(3 as i32 * i + 0 as i32) as usize];
-            let t3: f32 = (x0 - x3)
-                * MP3D_DCT_II_G_SEC[(3 as i32 * i + 1 as i32) as usize];
-            *x.offset(0 as i32 as isize) = t0 + t1;
-            *x
-                .offset(
-                    8 as i32 as isize,
-                ) = (t0 - t1)
-                * MP3D_DCT_II_G_SEC[(3 as i32 * i + 2 as i32) as usize];
-            *x.offset(16 as i32 as isize) = t3 + t2;
-            *x
-                .offset(
-                    24 as i32 as isize,
-                ) = (t3 - t2)
-                * MP3D_DCT_II_G_SEC[(3 as i32 * i + 2 as i32) as usize];
-            i += 1;
-            x = x.offset(1);
+
+fn mp3d_DCT_II(grbuf: &mut [mp3d_real_t], n: u32) {
+    #[cfg(not(feature = "forbid-unsafe"))]
+    let start = if simd::ENABLED { simd::DCT_II(grbuf, n) } else { 0 };
+    #[cfg(feature = "forbid-unsafe")]
+    let start = 0;
+    for k in start as usize..n as usize {
+        let mut t = [[const { real(0.) }; 8]; 4];
+        let y = &mut grbuf[k..];
+        for i in 0..8 {
+            let x0 = y[18 * i];
+            let x1 = y[18 * (15 - i)];
+            let x2 = y[18 * (16 + i)];
+            let x3 = y[18 * (31 - i)];
+            let t0 = x0 + x3;
+            let t1 = x1 + x2;
+            let t2 = (x1 - x2) * MP3D_DCT_II_G_SEC[3 * i];
+            let t3 = (x0 - x3) * MP3D_DCT_II_G_SEC[3 * i + 1];
+            t[0][i] = t0 + t1;
+            t[1][i] = (t0 - t1) * MP3D_DCT_II_G_SEC[3 * i + 2];
+            t[2][i] = t3 + t2;
+            t[3][i] = (t3 - t2) * MP3D_DCT_II_G_SEC[3 * i + 2];
         }
-        x = t.as_flattened_mut().as_mut_ptr();
-        i = 0 as i32;
-        while i < 4 as i32 {
-            let mut x0_0: f32 = *x.offset(0 as i32 as isize);
-            let mut x1_0: f32 = *x.offset(1 as i32 as isize);
-            let mut x2_0: f32 = *x.offset(2 as i32 as isize);
-            let mut x3_0: f32 = *x.offset(3 as i32 as isize);
-            let mut x4: f32 = *x.offset(4 as i32 as isize);
-            let mut x5: f32 = *x.offset(5 as i32 as isize);
-            let mut x6: f32 = *x.offset(6 as i32 as isize);
-            let mut x7: f32 = *x.offset(7 as i32 as isize);
-            let mut xt: f32 = 0.;
-            xt = x0_0 - x7;
-            x0_0 += x7;
-            x7 = x1_0 - x6;
-            x1_0 += x6;
-            x6 = x2_0 - x5;
-            x2_0 += x5;
-            x5 = x3_0 - x4;
-            x3_0 += x4;
-            x4 = x0_0 - x3_0;
-            x0_0 += x3_0;
-            x3_0 = x1_0 - x2_0;
-            x1_0 += x2_0;
-            *x.offset(0 as i32 as isize) = x0_0 + x1_0;
-            *x.offset(4 as i32 as isize) = (x0_0 - x1_0) * 0.70710677f32;
-            x5 = x5 + x6;
-            x6 = (x6 + x7) * 0.70710677f32;
-            x7 = x7 + xt;
-            x3_0 = (x3_0 + x4) * 0.70710677f32;
-            x5 -= x7 * 0.198912367f32;
-            x7 += x5 * 0.382683432f32;
-            x5 -= x7 * 0.198912367f32;
-            x0_0 = xt - x6;
+        for x in &mut t {
+            let [mut x0, mut x1, mut x2, mut x3, mut x4, mut x5, mut x6, mut x7] = *x;
+            let mut xt = x0 - x7;
+            x0 += x7;
+            x7 = x1 - x6;
+            x1 += x6;
+            x6 = x2 - x5;
+            x2 += x5;
+            x5 = x3 - x4;
+            x3 += x4;
+            x4 = x0 - x3;
+            x0 += x3;
+            x3 = x1 - x2;
+            x1 += x2;
+            x[0] = x0 + x1;
+            x[4] = (x0 - x1) * const { real(0.70710677) };
+            x5 += x6;
+            x6 = (x6 + x7) * const { real(0.70710677) };
+            x7 += xt;
+            x3 = (x3 + x4) * const { real(0.70710677) };
+            x5 -= x7 * const { real(0.198912367) };
+            x7 += x5 * const { real(0.382683432) };
+            x5 -= x7 * const { real(0.198912367) };
+            x0 = xt - x6;
             xt += x6;
-            *x.offset(1 as i32 as isize) = (xt + x7) * 0.50979561f32;
-            *x.offset(2 as i32 as isize) = (x4 + x3_0) * 0.54119611f32;
-            *x.offset(3 as i32 as isize) = (x0_0 - x5) * 0.60134488f32;
-            *x.offset(5 as i32 as isize) = (x0_0 + x5) * 0.89997619f32;
-            *x.offset(6 as i32 as isize) = (x4 - x3_0) * 1.30656302f32;
-            *x.offset(7 as i32 as isize) = (xt - x7) * 2.56291556f32;
-            i += 1;
-            x = x.offset(8 as i32 as isize);
+            x[1] = (xt + x7) * const { real(0.50979561) };
+            x[2] = (x4 + x3) * const { real(0.54119611) };
+            x[3] = (x0 - x5) * const { real(0.60134488) };
+            x[5] = (x0 + x5) * const { real(0.89997619) };
+            x[6] = (x4 - x3) * const { real(1.30656302) };
+            x[7] = (xt - x7) * const { real(2.56291556) };
         }
-        i = 0 as i32;
-        while i < 7 as i32 {
-            *y
-                .offset(
-                    (0 as i32 * 18 as i32) as isize,
-                ) = t[0 as i32 as usize][i as usize];
-            *y
-                .offset(
-                    (1 as i32 * 18 as i32) as isize,
-                ) = t[2 as i32 as usize][i as usize]
-                + t[3 as i32 as usize][i as usize]
-                + t[3 as i32 as usize][(i + 1 as i32) as usize];
-            *y
-                .offset(
-                    (2 as i32 * 18 as i32) as isize,
-                ) = t[1 as i32 as usize][i as usize]
-                + t[1 as i32 as usize][(i + 1 as i32) as usize];
-            *y
-                .offset(
-                    (3 as i32 * 18 as i32) as isize,
-                ) = t[2 as i32 as usize][(i + 1 as i32) as usize]
-                + t[3 as i32 as usize][i as usize]
-                + t[3 as i32 as usize][(i + 1 as i32) as usize];
-            i += 1;
-            y = y.offset((4 as i32 * 18 as i32) as isize);
+        for i in 0..7 {
+            y[18 * 4 * i] = t[0][i];
+            y[18 * (4 * i + 1)] = t[2][i] + t[3][i] + t[3][i + 1];
+            y[18 * (4 * i + 2)] = t[1][i] + t[1][i + 1];
+            y[18 * (4 * i + 3)] = t[2][i + 1] + t[3][i] + t[3][i + 1];
         }
-        *y
-            .offset(
-                (0 as i32 * 18 as i32) as isize,
-            ) = t[0 as i32 as usize][7 as i32 as usize];
-        *y
-            .offset(
-                (1 as i32 * 18 as i32) as isize,
-            ) = t[2 as i32 as usize][7 as i32 as usize]
-            + t[3 as i32 as usize][7 as i32 as usize];
-        *y
-            .offset(
-                (2 as i32 * 18 as i32) as isize,
-            ) = t[1 as i32 as usize][7 as i32 as usize];
-        *y
-            .offset(
-                (3 as i32 * 18 as i32) as isize,
-            ) = t[3 as i32 as usize][7 as i32 as usize];
-        k += 1;
+        y[18 * 28] = t[0][7];
+        y[18 * 29] = t[2][7] + t[3][7];
+        y[18 * 30] = t[1][7];
+        y[18 * 31] = t[3][7];
     }
 }
 
-fn mp3d_scale_pcm(sample: f32) -> f32 {
-    sample * (1f32/32768f32)
-}
-
-unsafe fn mp3d_synth_pair(
-    pcm: &mut [mp3d_sample_t],
+fn mp3d_synth_pair<P: Pcm>(
+    pcm: &mut P,
+    k: usize,
     nch: u32,
-    mut z: *const f32,
+    ch: u32,
+    z: &[mp3d_real_t],
 ) {
-    let mut a: f32 = 0.;
-    a = (*z.offset((14 as i32 * 64 as i32) as isize)
-        - *z.offset(0 as i32 as isize)) * 29 as i32 as f32;
-    a
-        += (*z.offset((1 as i32 * 64 as i32) as isize)
-            + *z.offset((13 as i32 * 64 as i32) as isize))
-            * 213 as i32 as f32;
-    a
-        += (*z.offset((12 as i32 * 64 as i32) as isize)
-            - *z.offset((2 as i32 * 64 as i32) as isize))
-            * 459 as i32 as f32;
-    a
-        += (*z.offset((3 as i32 * 64 as i32) as isize)
-            + *z.offset((11 as i32 * 64 as i32) as isize))
-            * 2037 as i32 as f32;
-    a
-        += (*z.offset((10 as i32 * 64 as i32) as isize)
-            - *z.offset((4 as i32 * 64 as i32) as isize))
-            * 5153 as i32 as f32;
-    a
-        += (*z.offset((5 as i32 * 64 as i32) as isize)
-            + *z.offset((9 as i32 * 64 as i32) as isize))
-            * 6574 as i32 as f32;
-    a
-        += (*z.offset((8 as i32 * 64 as i32) as isize)
-            - *z.offset((6 as i32 * 64 as i32) as isize))
-            * 37489 as i32 as f32;
-    a
-        += *z.offset((7 as i32 * 64 as i32) as isize)
-            * 75038 as i32 as f32;
-    pcm[0] = mp3d_scale_pcm(a);
-    z = z.offset(2 as i32 as isize);
-    a = *z.offset((14 as i32 * 64 as i32) as isize)
-        * 104 as i32 as f32;
-    a
-        += *z.offset((12 as i32 * 64 as i32) as isize)
-            * 1567 as i32 as f32;
-    a
-        += *z.offset((10 as i32 * 64 as i32) as isize)
-            * 9727 as i32 as f32;
-    a
-        += *z.offset((8 as i32 * 64 as i32) as isize)
-            * 64019 as i32 as f32;
-    a
-        += *z.offset((6 as i32 * 64 as i32) as isize)
-            * -(9975 as i32) as f32;
-    a
-        += *z.offset((4 as i32 * 64 as i32) as isize)
-            * -(45 as i32) as f32;
-    a
-        += *z.offset((2 as i32 * 64 as i32) as isize)
-            * 146 as i32 as f32;
-    a
-        += *z.offset((0 as i32 * 64 as i32) as isize)
-            * -(5 as i32) as f32;
-    pcm[(16 * nch) as usize] = mp3d_scale_pcm(a);
+    let mut a = (z[14 * 64] - z[0]) * win(29.);
+    a += (z[64] + z[13 * 64]) * win(213.);
+    a += (z[12 * 64] - z[2 * 64]) * win(459.);
+    a += (z[3 * 64] + z[11 * 64]) * win(2037.);
+    a += (z[10 * 64] - z[4 * 64]) * win(5153.);
+    a += (z[5 * 64] + z[9 * 64]) * win(6574.);
+    a += (z[8 * 64] - z[6 * 64]) * win(37489.);
+    a += z[7 * 64] * win(75038.);
+    pcm.set(nch, ch, k, pcm_sample(a));
+    let z = &z[2..];
+    let mut a = z[14 * 64] * win(104.);
+    a += z[12 * 64] * win(1567.);
+    a += z[10 * 64] * win(9727.);
+    a += z[8 * 64] * win(64019.);
+    a += z[6 * 64] * win(-9975.);
+    a += z[4 * 64] * win(-45.);
+    a += z[2 * 64] * win(146.);
+    a += z[0] * win(-5.);
+    pcm.set(nch, ch, k + 16, pcm_sample(a));
 }
-unsafe fn mp3d_synth(
-    xl: *mut f32,
-    dstl: &mut [mp3d_sample_t],
+
+fn mp3d_synth<P: Pcm>(
+    xl: &[mp3d_real_t],
+    pcm: &mut P,
+    k: usize,
     nch: u32,
-    lins: *mut f32,
+    lins: &mut [mp3d_real_t],
 ) {
-    let mut i: i32 = 0;
-    let xr: *mut f32 = xl
-        .offset((576 * (nch - 1)) as isize);
-    let dstr_off = (nch-1) as usize;
-
-    let zlin: *mut f32 = lins
-        .offset((15 as i32 * 64 as i32) as isize);
-    let mut w: *const f32 = MP3D_SYNTH_G_WIN.as_ptr();
-    *zlin
-        .offset(
-            (4 as i32 * 15 as i32) as isize,
-        ) = *xl.offset((18 as i32 * 16 as i32) as isize);
-    *zlin
-        .offset(
-            (4 as i32 * 15 as i32 + 1 as i32) as isize,
-        ) = *xr.offset((18 as i32 * 16 as i32) as isize);
-    *zlin
-        .offset(
-            (4 as i32 * 15 as i32 + 2 as i32) as isize,
-        ) = *xl.offset(0 as i32 as isize);
-    *zlin
-        .offset(
-            (4 as i32 * 15 as i32 + 3 as i32) as isize,
-        ) = *xr.offset(0 as i32 as isize);
-    *zlin
-        .offset(
-            (4 as i32 * 31 as i32) as isize,
-        ) = *xl
-        .offset((1 as i32 + 18 as i32 * 16 as i32) as isize);
-    *zlin
-        .offset(
-            (4 as i32 * 31 as i32 + 1 as i32) as isize,
-        ) = *xr
-        .offset((1 as i32 + 18 as i32 * 16 as i32) as isize);
-    *zlin
-        .offset(
-            (4 as i32 * 31 as i32 + 2 as i32) as isize,
-        ) = *xl.offset(1 as i32 as isize);
-    *zlin
-        .offset(
-            (4 as i32 * 31 as i32 + 3 as i32) as isize,
-        ) = *xr.offset(1 as i32 as isize);
-    mp3d_synth_pair(
-        &mut dstl[dstr_off..],
-        nch,
-        lins
-            .offset((4 as i32 * 15 as i32) as isize)
-            .offset(1 as i32 as isize),
-    );
-    mp3d_synth_pair(
-        &mut dstl[dstr_off+(32 * nch) as usize..],
-        nch,
-        lins
-            .offset((4 as i32 * 15 as i32) as isize)
-            .offset(64 as i32 as isize)
-            .offset(1 as i32 as isize),
-    );
-    mp3d_synth_pair(
-        dstl,
-        nch,
-        lins.offset((4 as i32 * 15 as i32) as isize),
-    );
-    mp3d_synth_pair(
-        &mut dstl[(32 * nch) as usize..],
-        nch,
-        lins
-            .offset((4 as i32 * 15 as i32) as isize)
-            .offset(64 as i32 as isize),
-    );
-    i = 14;
-    while i >= 0 {
-        let mut a: [f32; 4] = [0.; 4];
-        let mut b: [f32; 4] = [0.; 4];
-        *zlin
-            .offset(
-                (4 * i) as isize,
-            ) = *xl.offset((18 * (31 - i)) as isize);
-        *zlin
-            .offset(
-                (4 * i + 1) as isize,
-            ) = *xr.offset((18 * (31 - i)) as isize);
-        *zlin
-            .offset(
-                (4 * i + 2) as isize,
-            ) = *xl
-            .offset(
-                (1 + 18 * (31 - i)) as isize,
-            );
-        *zlin
-            .offset(
-                (4 * i + 3) as isize,
-            ) = *xr
-            .offset(
-                (1 + 18 * (31 - i)) as isize,
-            );
-        *zlin
-            .offset(
-                (4 * (i + 16)) as isize,
-            ) = *xl
-            .offset(
-                (1 + 18 * (1 + i)) as isize,
-            );
-        *zlin
-            .offset(
-                (4 * (i + 16) + 1) as isize,
-            ) = *xr
-            .offset(
-                (1 + 18 * (1 + i)) as isize,
-            );
-        *zlin
-            .offset(
-                (4 * (i - 16) + 2) as isize,
-            ) = *xl.offset((18 * (1 + i)) as isize);
-        *zlin
-            .offset(
-                (4 * (i - 16) + 3) as isize,
-            ) = *xr.offset((18 * (1 + i)) as isize);
-        let mut j: i32 = 0;
-        let fresh22 = w;
-        w = w.offset(1);
-        let w0: f32 = *fresh22;
-        let fresh23 = w;
-        w = w.offset(1);
-        let w1: f32 = *fresh23;
-        let vz: *mut f32 = zlin
-            .offset(
-                (4 * i - 0 * 64) as isize,
-            );
-        let vy: *mut f32 = zlin
-            .offset(
-                (4 * i
-                    - (15 - 0) * 64)
-                    as isize,
-            );
-        j = 0;
-        while j < 4 {
-            b[j as usize] = *vz.offset(j as isize) * w1 + *vy.offset(j as isize) * w0;
-            a[j as usize] = *vz.offset(j as isize) * w0 - *vy.offset(j as isize) * w1;
-            j += 1;
-        }
-        let mut j_0: i32 = 0;
-        let fresh24 = w;
-        w = w.offset(1);
-        let w0_0: f32 = *fresh24;
-        let fresh25 = w;
-        w = w.offset(1);
-        let w1_0: f32 = *fresh25;
-        let vz_0: *mut f32 = zlin
-            .offset(
-                (4 * i - 1 * 64) as isize,
-            );
-        let vy_0: *mut f32 = zlin
-            .offset(
-                (4 * i
-                    - (15 - 1) * 64)
-                    as isize,
-            );
-        j_0 = 0 as i32;
-        while j_0 < 4 as i32 {
-            b[j_0 as usize]
-                += *vz_0.offset(j_0 as isize) * w1_0 + *vy_0.offset(j_0 as isize) * w0_0;
-            a[j_0 as usize]
-                += *vy_0.offset(j_0 as isize) * w1_0 - *vz_0.offset(j_0 as isize) * w0_0;
-            j_0 += 1;
-        }
-        let mut j_1: i32 = 0;
-        let fresh26 = w;
-        w = w.offset(1);
-        let w0_1: f32 = *fresh26;
-        let fresh27 = w;
-        w = w.offset(1);
-        let w1_1: f32 = *fresh27;
-        let vz_1: *mut f32 = zlin
-            .offset(
-                (4 * i - 2 * 64) as isize,
-            );
-        let vy_1: *mut f32 = zlin
-            .offset(
-                (4 * i
-                    - (15 - 2) * 64)
-                    as isize,
-            );
-        j_1 = 0 as i32;
-        while j_1 < 4 as i32 {
-            b[j_1 as usize]
-                += *vz_1.offset(j_1 as isize) * w1_1 + *vy_1.offset(j_1 as isize) * w0_1;
-            a[j_1 as usize]
-                += *vz_1.offset(j_1 as isize) * w0_1 - *vy_1.offset(j_1 as isize) * w1_1;
-            j_1 += 1;
-        }
-        let mut j_2: i32 = 0;
-        let fresh28 = w;
-        w = w.offset(1);
-        let w0_2: f32 = *fresh28;
-        let fresh29 = w;
-        w = w.offset(1);
-        let w1_2: f32 = *fresh29;
-        let vz_2: *mut f32 = zlin
-            .offset(
-                (4 * i - 3 * 64) as isize,
-            );
-        let vy_2: *mut f32 = zlin
-            .offset(
-                (4 * i
-                    - (15 - 3) * 64)
-                    as isize,
-            );
-        j_2 = 0 as i32;
-        while j_2 < 4 as i32 {
-            b[j_2 as usize]
-                += *vz_2.offset(j_2 as isize) * w1_2 + *vy_2.offset(j_2 as isize) * w0_2;
-            a[j_2 as usize]
-                += *vy_2.offset(j_2 as isize) * w1_2 - *vz_2.offset(j_2 as isize) * w0_2;
-            j_2 += 1;
-        }
-        let mut j_3: i32 = 0;
-        let fresh30 = w;
-        w = w.offset(1);
-        let w0_3: f32 = *fresh30;
-        let fresh31 = w;
-        w = w.offset(1);
-        let w1_3: f32 = *fresh31;
-        let vz_3: *mut f32 = zlin
-            .offset(
-                (4 * i - 4 * 64) as isize,
-            );
-        let vy_3: *mut f32 = zlin
-            .offset(
-                (4 * i
-                    - (15 - 4) * 64)
-                    as isize,
-            );
-        j_3 = 0 as i32;
-        while j_3 < 4 as i32 {
-            b[j_3 as usize]
-                += *vz_3.offset(j_3 as isize) * w1_3 + *vy_3.offset(j_3 as isize) * w0_3;
-            a[j_3 as usize]
-                += *vz_3.offset(j_3 as isize) * w0_3 - *vy_3.offset(j_3 as isize) * w1_3;
-            j_3 += 1;
-        }
-        let mut j_4: i32 = 0;
-        let fresh32 = w;
-        w = w.offset(1);
-        let w0_4: f32 = *fresh32;
-        let fresh33 = w;
-        w = w.offset(1);
-        let w1_4: f32 = *fresh33;
-        let vz_4: *mut f32 = zlin
-            .offset(
-                (4 * i - 5 * 64) as isize,
-            );
-        let vy_4: *mut f32 = zlin
-            .offset(
-                (4 * i
-                    - (15 - 5) * 64)
-                    as isize,
-            );
-        j_4 = 0 as i32;
-        while j_4 < 4 as i32 {
-            b[j_4 as usize]
-                += *vz_4.offset(j_4 as isize) * w1_4 + *vy_4.offset(j_4 as isize) * w0_4;
-            a[j_4 as usize]
-                += *vy_4.offset(j_4 as isize) * w1_4 - *vz_4.offset(j_4 as isize) * w0_4;
-            j_4 += 1;
-        }
-        let mut j_5: i32 = 0;
-        let fresh34 = w;
-        w = w.offset(1);
-        let w0_5: f32 = *fresh34;
-        let fresh35 = w;
-        w = w.offset(1);
-        let w1_5: f32 = *fresh35;
-        let vz_5: *mut f32 = zlin
-            .offset(
-                (4 * i - 6 * 64) as isize,
-            );
-        let vy_5: *mut f32 = zlin
-            .offset(
-                (4 * i
-                    - (15 - 6) * 64)
-                    as isize,
-            );
-        j_5 = 0 as i32;
-        while j_5 < 4 as i32 {
-            b[j_5 as usize]
-                += *vz_5.offset(j_5 as isize) * w1_5 + *vy_5.offset(j_5 as isize) * w0_5;
-            a[j_5 as usize]
-                += *vz_5.offset(j_5 as isize) * w0_5 - *vy_5.offset(j_5 as isize) * w1_5;
-            j_5 += 1;
+    // Row 15 of `lins`, where this call's input is written in front of the 15 rows kept from earlier calls
+    const ZLIN: usize = 15 * 64;
+    let xr = &xl[576 * (nch as usize - 1)..];
+    let r = nch - 1;
+    let lins: &mut [mp3d_real_t; 15 * 64 + 128] = (&mut lins[..15 * 64 + 128]).try_into().unwrap();
+    lins[ZLIN + 4 * 15] = xl[18 * 16];
+    lins[ZLIN + 4 * 15 + 1] = xr[18 * 16];
+    lins[ZLIN + 4 * 15 + 2] = xl[0];
+    lins[ZLIN + 4 * 15 + 3] = xr[0];
+    lins[ZLIN + 4 * 31] = xl[1 + 18 * 16];
+    lins[ZLIN + 4 * 31 + 1] = xr[1 + 18 * 16];
+    lins[ZLIN + 4 * 31 + 2] = xl[1];
+    lins[ZLIN + 4 * 31 + 3] = xr[1];
+    #[cfg(not(feature = "forbid-unsafe"))]
+    if simd::ENABLED {
+        let mut acc: [mp3d_acc_t; 8] = [acc_zero(); 8];
+        simd::synth_pair(lins, &mut acc);
+        let [a0, a1, a2, a3, b0, b1, b2, b3] = acc;
+        pcm.set(nch, r, k, pcm_sample(a1));
+        pcm.set(nch, r, k + 16, pcm_sample(b1));
+        pcm.set(nch, r, k + 32, pcm_sample(a3));
+        pcm.set(nch, r, k + 48, pcm_sample(b3));
+        pcm.set(nch, 0, k, pcm_sample(a0));
+        pcm.set(nch, 0, k + 16, pcm_sample(b0));
+        pcm.set(nch, 0, k + 32, pcm_sample(a2));
+        pcm.set(nch, 0, k + 48, pcm_sample(b2));
+        let mut acc: [[mp3d_acc_t; 8]; 15] = [[acc_zero(); 8]; 15];
+        simd::synth(xl, xr, lins, &mut acc);
+        for (n, &[a0, a1, a2, a3, b0, b1, b2, b3]) in acc.iter().enumerate() {
+            pcm.set(nch, r, k + 15 - n, pcm_sample(a1));
+            pcm.set(nch, r, k + 17 + n, pcm_sample(b1));
+            pcm.set(nch, 0, k + 15 - n, pcm_sample(a0));
+            pcm.set(nch, 0, k + 17 + n, pcm_sample(b0));
+            pcm.set(nch, r, k + 47 - n, pcm_sample(a3));
+            pcm.set(nch, r, k + 49 + n, pcm_sample(b3));
+            pcm.set(nch, 0, k + 47 - n, pcm_sample(a2));
+            pcm.set(nch, 0, k + 49 + n, pcm_sample(b2));
         }
-        let mut j_6: i32 = 0;
-        let fresh36 = w;
-        w = w.offset(1);
-        let w0_6: f32 = *fresh36;
-        let fresh37 = w;
-        w = w.offset(1);
-        let w1_6: f32 = *fresh37;
-        let vz_6: *mut f32 = zlin
-            .offset(
-                (4 * i - 7 * 64) as isize,
-            );
-        let vy_6: *mut f32 = zlin
-            .offset(
-                (4 * i
-                    - (15 - 7) * 64)
-                    as isize,
-            );
-        j_6 = 0 as i32;
-        while j_6 < 4 as i32 {
-            b[j_6 as usize]
-                += *vz_6.offset(j_6 as isize) * w1_6 + *vy_6.offset(j_6 as isize) * w0_6;
-            a[j_6 as usize]
-                += *vy_6.offset(j_6 as isize) * w1_6 - *vz_6.offset(j_6 as isize) * w0_6;
-            j_6 += 1;
+        return;
+    }
+    mp3d_synth_pair(pcm, k, nch, r, &lins[4 * 15 + 1..]);
+    mp3d_synth_pair(pcm, k + 32, nch, r, &lins[4 * 15 + 64 + 1..]);
+    mp3d_synth_pair(pcm, k, nch, 0, &lins[4 * 15..]);
+    mp3d_synth_pair(pcm, k + 32, nch, 0, &lins[4 * 15 + 64..]);
+    for i in (0..15).rev() {
+        let mut a: [mp3d_acc_t; 4] = [acc_zero(); 4];
+        let mut b: [mp3d_acc_t; 4] = [acc_zero(); 4];
+        let z = ZLIN + 4 * i;
+        lins[z] = xl[18 * (31 - i)];
+        lins[z + 1] = xr[18 * (31 - i)];
+        lins[z + 2] = xl[1 + 18 * (31 - i)];
+        lins[z + 3] = xr[1 + 18 * (31 - i)];
+        lins[z + 64] = xl[1 + 18 * (1 + i)];
+        lins[z + 64 + 1] = xr[1 + 18 * (1 + i)];
+        lins[z - 64 + 2] = xl[18 * (1 + i)];
+        lins[z - 64 + 3] = xr[18 * (1 + i)];
+        let w: &[mp3d_win_t] = &MP3D_SYNTH_G_WIN[16 * (14 - i)..][..16];
+        for m in 0..8 {
+            let w0 = w[2 * m];
+            let w1 = w[2 * m + 1];
+            let vz = &lins[z - m * 64..][..4];
+            let vy = &lins[z - (15 - m) * 64..][..4];
+            for j in 0..4 {
+                let bj = vz[j] * w1 + vy[j] * w0;
+                let aj = if m % 2 == 0 {
+                    vz[j] * w0 - vy[j] * w1
+                } else {
+                    vy[j] * w1 - vz[j] * w0
+                };
+                if m == 0 {
+                    b[j] = bj;
+                    a[j] = aj;
+                } else {
+                    b[j] += bj;
+                    a[j] += aj;
+                }
+            }
         }
-        dstl[dstr_off+((15 - i as u32) * nch) as usize] = mp3d_scale_pcm(a[1]);
-        dstl[dstr_off+((17 + i as u32) * nch) as usize] = mp3d_scale_pcm(b[1]);
-        dstl[((15 - i as u32) * nch) as usize] = mp3d_scale_pcm(a[0]);
-        dstl[((17 + i as u32) * nch) as usize] = mp3d_scale_pcm(b[0 as i32 as usize]);
-        dstl[dstr_off+((47 - i as u32) * nch) as usize] = mp3d_scale_pcm(a[3 as i32 as usize]);
-        dstl[dstr_off+((49 + i as u32) * nch) as usize] = mp3d_scale_pcm(b[3 as i32 as usize]);
-        dstl[((47 - i as u32) * nch) as usize] = mp3d_scale_pcm(a[2 as i32 as usize]);
-        dstl[((49 + i as u32) * nch) as usize] = mp3d_scale_pcm(b[2 as i32 as usize]);
-        i -= 1;
+        pcm.set(nch, r, k + 15 - i, pcm_sample(a[1]));
+        pcm.set(nch, r, k + 17 + i, pcm_sample(b[1]));
+        pcm.set(nch, 0, k + 15 - i, pcm_sample(a[0]));
+        pcm.set(nch, 0, k + 17 + i, pcm_sample(b[0]));
+        pcm.set(nch, r, k + 47 - i, pcm_sample(a[3]));
+        pcm.set(nch, r, k + 49 + i, pcm_sample(b[3]));
+        pcm.set(nch, 0, k + 47 - i, pcm_sample(a[2]));
+        pcm.set(nch, 0, k + 49 + i, pcm_sample(b[2]));
     }
 }
-unsafe fn mp3d_synth_granule(
-    qmf_state: *mut f32,
-    grbuf: *mut f32,
+
+fn mp3d_synth_granule<P: Pcm>(
+    qmf_state: &mut [mp3d_real_t; 960],
+    grbuf: &mut [mp3d_real_t],
     nbands: u32,
     nch: u32,
-    pcm: &mut [mp3d_sample_t],
-    lins: *mut f32,
+    pcm: &mut P,
+    k: usize,
+    lins: &mut [mp3d_real_t],
 ) {
-    let mut i: usize = 0;
-    while i < nch as usize {
-        mp3d_DCT_II(grbuf.offset((576 * i) as isize), nbands);
-        i += 1;
+    for ch in 0..nch as usize {
+        mp3d_DCT_II(&mut grbuf[576 * ch..], nbands);
     }
-    memcpy(
-        lins as *mut (),
-        qmf_state as *const (),
-        (::core::mem::size_of::<f32>() as usize)
-            .wrapping_mul(15 as i32 as usize)
-            .wrapping_mul(64 as i32 as usize),
-    );
-    i = 0;
-    while i < nbands as usize {
-        mp3d_synth(
-            grbuf.offset(i as isize),
-            &mut pcm[32 * nch as usize * i..],
-            nch,
-            lins.offset((i * 64) as isize),
-        );
-        i += 2;
+    lins[..15 * 64].copy_from_slice(qmf_state);
+    for i in (0..nbands as usize).step_by(2) {
+        mp3d_synth(&grbuf[i..], pcm, k + 32 * i, nch, &mut lins[64 * i..]);
     }
-    memcpy(
-        qmf_state as *mut (),
-        lins.offset((nbands * 64) as isize) as *const (),
-        (::core::mem::size_of::<f32>() as usize)
-            .wrapping_mul(15 as i32 as usize)
-            .wrapping_mul(64 as i32 as usize),
-    );
+    qmf_state.copy_from_slice(&lins[64 * nbands as usize..][..15 * 64]);
 }
 
 fn mp3d_match_frame(
     hdr: &[u8],
     frame_bytes: usize,
 ) -> bool {
-    let mut i: usize = 0;
-    let mut nmatch: i32 = 0;
-    nmatch = 0 as i32;
-    while nmatch < 10 as i32 {
+    let mut i = 0;
+    for nmatch in 0..10 {
         i += hdr_frame_bytes(&hdr[i..], frame_bytes) + hdr_padding(&hdr[i..]);
         if i + 4 > hdr.len() {
             return nmatch > 0;
@@ -2058,7 +1309,6 @@ fn mp3d_match_frame(
         if !hdr_compare(hdr, &hdr[i..]) {
             return false;
         }
-        nmatch += 1;
     }
     true
 }
@@ -2071,7 +1321,7 @@ fn mp3d_find_frame(
     let mp3_bytes = mp3.len();
     let mut i: usize = 0;
     let mut k: usize = 0;
-    while i < mp3_bytes - 4 {
+    while i + 4 < mp3_bytes {
         if hdr_valid(mp3) {
             let mut frame_bytes = hdr_frame_bytes(mp3, *free_format_bytes);
             let mut frame_and_padding = frame_bytes + hdr_padding(mp3);
@@ -2109,146 +1359,183 @@ fn mp3dec_init(dec: &mut mp3dec_t) {
     dec.header[0] = 0;
 }
 
-pub unsafe fn mp3dec_decode_frame(
+pub fn mp3dec_decode_frame<P: Pcm>(
     dec: &mut mp3dec_t,
     mp3: &[u8],
-    mut pcm: &mut [mp3d_sample_t],
+    pcm: Option<&mut P>,
+    frame_scratch: &mut mp3dec_frame_scratch_t,
     info: &mut mp3dec_frame_info_t,
+    verify_crc: bool,
+    keep_reservoir: bool,
+    detect_vbr_tag: bool,
 ) -> i32 {
-    let mut i: usize = 0;
-    let mut igr = 0u32;
-    let mut frame_size: usize = 0;
-    let mut success: i32 = 1 as i32;
-    let mut scratch: mp3dec_scratch_t = mp3dec_scratch_t {
-        grbuf: [[0.; 576]; 2],
-        scf: [0.; 40],
-        syn: [[0.; 64]; 33],
-        ist_pos: [[0; 39]; 2],
-    };
-    let mut scratch_maindata = [0u8; 2815];
-    let mut scratch_bs = bs_t {
-        buf: &[],
-        pos: 0,
-        limit: 0,
-    };
-    let mut scratch_gr_info = [L3_gr_info_t {
-        sfbtab: core::ptr::null(),
-        part_23_length: 0,
-        big_values: 0,
-        scalefac_compress: 0,
-        global_gain: 0,
-        block_type: 0,
-        mixed_block_flag: 0,
-        n_long_sfb: 0,
-        n_short_sfb: 0,
-        table_select: [0; 3],
-        region_count: [0; 3],
-        subblock_gain: [0; 3],
-        preflag: 0,
-        scalefac_scale: 0,
-        count1_table: 0,
-        scfsi: 0,
-    }; 4];
-    if mp3.len() > 4
-        && (*dec).header[0 as i32 as usize] as i32 == 0xff as i32
-        && hdr_compare(&dec.header, mp3)
-    {
-        frame_size = hdr_frame_bytes(mp3, (*dec).free_format_bytes) + hdr_padding(mp3);
-        if frame_size != mp3.len()
-            && (frame_size + 4 > mp3.len()
-                || !hdr_compare(mp3, &mp3[frame_size..]))
-        {
+    let mp3dec_frame_scratch_t {
+        scratch,
+        maindata: scratch_maindata,
+        gr_info: scratch_gr_info,
+    } = frame_scratch;
+    if dec.skip_bytes != 0 {
+        // Still in the middle of an ID3v2 tag
+        let n = dec.skip_bytes.min(mp3.len());
+        dec.skip_bytes -= n;
+        info.frame_bytes = n;
+        return 0;
+    }
+    let mut i = 0;
+    let mut frame_size = 0;
+    if mp3.len() > 4 && dec.header[0] == 0xff && hdr_compare(&dec.header, mp3) {
+        frame_size = hdr_frame_bytes(mp3, dec.free_format_bytes) + hdr_padding(mp3);
+        if frame_size != mp3.len() && (frame_size + 4 > mp3.len() || !hdr_compare(mp3, &mp3[frame_size..])) {
             frame_size = 0;
         }
     }
-    if frame_size == 0 {
+    let first_frame = frame_size == 0;
+    if first_frame {
+        let tag_allowed = dec.tag_allowed;
         *dec = mp3dec_t::new();
-        i = mp3d_find_frame(
-            mp3,
-            &mut (*dec).free_format_bytes,
-            &mut frame_size,
-        );
+        if let Some(tag_len) = id3::tag_len(mp3).filter(|_| tag_allowed) {
+            // Skip the tag without searching it for frames, as embedded pictures often contain false syncs
+            info.frame_bytes = tag_len.min(mp3.len());
+            dec.skip_bytes = tag_len - info.frame_bytes;
+            return 0;
+        }
+        dec.tag_allowed = false;
+        i = mp3d_find_frame(mp3, &mut dec.free_format_bytes, &mut frame_size);
         if frame_size == 0 || i + frame_size > mp3.len() {
-            (*info).frame_bytes = i;
-            return 0 as i32;
+            info.frame_bytes = i;
+            return 0;
         }
     }
     let hdr = &mp3[i..];
-    memcpy(
-        ((*dec).header).as_mut_ptr() as *mut (),
-        hdr.as_ptr() as *const (),
-        4 as i32 as usize,
-    );
-    (*info).frame_bytes = i + frame_size;
-    (*info).frame_offset = i;
-    (*info).channels = if hdr[3] & 0xc0 == 0xc0 {
-        1
-    } else {
-        2
-    };
-    (*info).hz = hdr_sample_rate_hz(hdr) as i32;
-    (*info)
-        .layer = 4
-        - (hdr[1] >> 1
-            & 3);
-    (*info).bitrate_kbps = hdr_bitrate_kbps(hdr) as i32;
-    if pcm.is_empty() {
-        return hdr_frame_samples(hdr) as i32;
+    dec.header.copy_from_slice(&hdr[..4]);
+    info.frame_bytes = i + frame_size;
+    info.frame_offset = i;
+    info.channels = if hdr[3] & 0xc0 == 0xc0 { 1 } else { 2 };
+    info.hz = hdr_sample_rate_hz(hdr) as i32;
+    info.layer = 4 - (hdr[1] >> 1 & 3);
+    info.bitrate_kbps = hdr_bitrate_kbps(hdr) as i32;
+    info.crc_ok = true;
+    if first_frame && detect_vbr_tag {
+        info.vbr_info = VbrInfo::parse(&hdr[..frame_size]);
+        if info.vbr_info.is_some() {
+            // The main data of the first audio frame usually begins in this frame, so it still fills the reservoir
+            let side_info_bytes = match (hdr[1] & 0x8 != 0, info.channels) {
+                (true, 1) => 17,
+                (true, _) => 32,
+                (false, 1) => 9,
+                (false, _) => 17,
+            };
+            let main_data = 4 + if hdr[1] & 1 == 0 { 2 } else { 0 } + side_info_bytes;
+            L3_save_reservoir(dec, &bs_init(&hdr[main_data..], (frame_size - main_data) as i32));
+            return MP3D_VBR_TAG;
+        }
     }
-    let mut bs_frame = bs_init(
-        &hdr[4..],
-        (frame_size - 4) as i32,
-    );
-    if hdr[1] & 1 == 0 {
-        get_bits(&mut bs_frame, 16);
+    if pcm.is_none() && !(keep_reservoir && info.layer == 3) {
+        if !keep_reservoir {
+            // The skipped frame's main data never reaches the reservoir, so don't let the next frame use it
+            dec.reserv = 0;
+        }
+        return hdr_frame_samples(hdr) as i32;
     }
-    if (*info).layer == 3 {
-        let main_data_begin: i32 = L3_read_side_info(
-            &mut bs_frame,
-            &mut scratch_gr_info,
-            hdr,
-        );
-        if main_data_begin < 0 as i32
-            || bs_frame.pos > bs_frame.limit
-        {
+    let mut bs_frame = bs_init(&hdr[4..], (frame_size - 4) as i32);
+    let crc = if hdr[1] & 1 == 0 { get_bits(&mut bs_frame, 16) as i32 } else { -1 };
+    let mut success = true;
+    if info.layer == 3 {
+        let main_data_begin = L3_read_side_info(&mut bs_frame, scratch_gr_info, hdr);
+        if main_data_begin < 0 || bs_frame.pos > bs_frame.limit {
             mp3dec_init(dec);
-            return 0 as i32;
+            return MP3D_E_DECODE;
         }
-        success = L3_restore_reservoir(
-            dec,
-            &mut bs_frame,
-            &mut scratch_maindata,
-            &mut scratch_bs,
-            main_data_begin,
-        );
-        if success != 0 {
-            igr = 0;
-            while igr < (if hdr[1] & 0x8 != 0 { 2 } else { 1 })
-            {
-                scratch.grbuf.as_flattened_mut().fill(0f32);
-                L3_decode(
-                    dec,
-                    &mut scratch,
-                    &mut scratch_bs,
-                    &mut scratch_gr_info[(igr * (*info).channels) as usize..],
-                    (*info).channels,
-                );
+        info.crc_ok = mp3d_check_crc(hdr, crc, bs_frame.pos - 16);
+        if !info.crc_ok && verify_crc {
+            dec.reserv = 0;
+            return MP3D_E_CRC;
+        }
+        let mut scratch_bs = bs_init(&[], 0);
+        success = L3_restore_reservoir(dec, &bs_frame, scratch_maindata, &mut scratch_bs, main_data_begin);
+        let ngr = if hdr[1] & 0x8 != 0 { 2 } else { 1 };
+        let nch = info.channels as usize;
+        let Some(pcm) = pcm else {
+            // Pass over the granules without decoding them, keeping the main data that later frames refer back to
+            if success {
+                let granule_bits = scratch_gr_info[..ngr * nch]
+                    .iter()
+                    .map(|gr| gr.part_23_length as i32)
+                    .sum::<i32>();
+                scratch_bs.pos = (scratch_bs.pos + granule_bits).min(scratch_bs.limit);
+            }
+            L3_save_reservoir(dec, &scratch_bs);
+            return hdr_frame_samples(hdr) as i32;
+        };
+        if success {
+            for igr in 0..ngr {
+                scratch.grbuf.as_flattened_mut().fill(const { real(0.) });
+                L3_decode(dec, scratch, &mut scratch_bs, &scratch_gr_info[igr * nch..], info.channels);
                 mp3d_synth_granule(
-                    ((*dec).qmf_state).as_mut_ptr(),
-                    scratch.grbuf.as_flattened_mut().as_mut_ptr(),
+                    &mut dec.qmf_state,
+                    scratch.grbuf.as_flattened_mut(),
                     18,
-                    (*info).channels,
+                    info.channels,
                     pcm,
-                    scratch.syn.as_flattened_mut().as_mut_ptr(),
+                    576 * igr,
+                    scratch.syn.as_flattened_mut(),
                 );
-                igr += 1;
-                pcm = &mut pcm[576 * ((*info).channels as usize)..];
             }
         }
-        L3_save_reservoir(dec, &mut scratch_bs);
+        L3_save_reservoir(dec, &scratch_bs);
     } else {
-        return 0 as i32
+        let Some(pcm) = pcm else {
+            unreachable!("only Layer III frames are passed over with the reservoir kept")
+        };
+        let mut sci = L12_scale_info {
+            scf: [const { gain(0.) }; 192],
+            total_bands: 0,
+            stereo_bands: 0,
+            bitalloc: [0; 64],
+            scfcod: [0; 64],
+        };
+        let crc_end = L12_read_scale_info(hdr, &mut bs_frame, &mut sci);
+        if bs_frame.pos > bs_frame.limit {
+            mp3dec_init(dec);
+            return MP3D_E_DECODE;
+        }
+        info.crc_ok = mp3d_check_crc(hdr, crc, crc_end - 16);
+        if !info.crc_ok && verify_crc {
+            return MP3D_E_CRC;
+        }
+        scratch.grbuf.as_flattened_mut().fill(const { real(0.) });
+        let mut pos = 0;
+        let mut k = 0;
+        for igr in 0..3 {
+            pos += L12_dequantize_granule(
+                &mut scratch.grbuf.as_flattened_mut()[pos..],
+                &mut bs_frame,
+                &sci,
+                (info.layer | 1) as usize,
+            );
+            if pos == 12 {
+                pos = 0;
+                L12_apply_scf_384(&sci, &sci.scf[igr..], scratch.grbuf.as_flattened_mut());
+                mp3d_synth_granule(
+                    &mut dec.qmf_state,
+                    scratch.grbuf.as_flattened_mut(),
+                    12,
+                    info.channels,
+                    pcm,
+                    k,
+                    scratch.syn.as_flattened_mut(),
+                );
+                scratch.grbuf.as_flattened_mut().fill(const { real(0.) });
+                k += 384;
+            }
+            if bs_frame.pos > bs_frame.limit {
+                mp3dec_init(dec);
+                return MP3D_E_DECODE;
+            }
+        }
+    }
+    if !success {
+        return MP3D_E_RESERVOIR;
     }
-    return (success as u32)
-        .wrapping_mul(hdr_frame_samples(&dec.header)) as i32;
+    hdr_frame_samples(&dec.header) as i32
 }
@@ -0,0 +1,693 @@
+//! The hot loops of the filterbanks over 4-lane vectors, as upstream's `HAVE_SIMD` paths. [`f4`] is SSE on x86_64,
+//! NEON on aarch64 and simd128 on wasm32 when the target feature is enabled. On x86_64, the kernels that have room
+//! for 8 lanes use AVX2 where available, see [`x86`].
+//!
+//! They only run when [`HAVE_SIMD`](super::HAVE_SIMD) says the pipeline computes in `f32`, so the tables are read
+//! through `f32` pointers. Each lane does the same operations in the same order as the scalar code, without fused
+//! multiply-adds, so the output is bit-identical to it.
+//!
+//! The entry points take the decoder's buffers as slices and check their lengths, so this module and [`x86`] hold
+//! all of the decoder's `unsafe` code. The `forbid-unsafe` feature leaves them out.
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64::*;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use core::arch::wasm32::*;
+
+use super::tables::*;
+use super::{mp3d_acc_t, mp3d_real_t};
+
+#[cfg(target_arch = "x86_64")]
+#[path = "x86.rs"]
+pub(crate) mod x86;
+
+/// Whether the kernels stand in for the scalar loops. The `no-simd` feature turns them off, as upstream's
+/// `MINIMP3_NO_SIMD`.
+pub(crate) const ENABLED: bool = super::HAVE_SIMD
+    && !cfg!(feature = "no-simd")
+    && cfg!(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        all(target_arch = "wasm32", target_feature = "simd128"),
+    ));
+
+/// The vector operations the kernels are written in.
+trait Vf32: Copy {
+    const LANES: usize;
+
+    unsafe fn ld(p: *const f32) -> Self;
+    unsafe fn st(self, p: *mut f32);
+    unsafe fn set(x: f32) -> Self;
+    unsafe fn add(self, b: Self) -> Self;
+    unsafe fn sub(self, b: Self) -> Self;
+    unsafe fn mul(self, b: Self) -> Self;
+    /// The lanes in reverse order.
+    unsafe fn rev(self) -> Self;
+
+    /// `*p` in the first four lanes, `*p.offset(stride)` in the next four.
+    unsafe fn set_rows(p: *const f32, stride: isize) -> Self;
+    /// Stores the first four lanes to `p` and the next four to `p.offset(stride)`.
+    unsafe fn st_rows(self, p: *mut f32, stride: isize);
+}
+
+/// Four `f32` lanes.
+#[derive(Copy, Clone)]
+pub(crate) struct f4(
+    #[cfg(target_arch = "x86_64")] __m128,
+    #[cfg(target_arch = "aarch64")] float32x4_t,
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))] v128,
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        all(target_arch = "wasm32", target_feature = "simd128"),
+    )))]
+    [f32; 4],
+);
+
+/// Loads and stores of two lanes, which [`f4`] needs beyond [`Vf32`].
+trait Pairs: Vf32 {
+    /// `p[0], p[1]` in the first two lanes and zeroes in the others.
+    unsafe fn ld2(p: *const f32) -> Self;
+    /// `p[0], p[1], q[0], q[1]`
+    unsafe fn ld2x2(p: *const f32, q: *const f32) -> Self;
+    /// Stores the first two lanes.
+    unsafe fn st2(self, p: *mut f32);
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Vf32 for f4 {
+    const LANES: usize = 4;
+
+    #[inline(always)]
+    unsafe fn ld(p: *const f32) -> Self {
+        f4(_mm_loadu_ps(p))
+    }
+
+    #[inline(always)]
+    unsafe fn st(self, p: *mut f32) {
+        _mm_storeu_ps(p, self.0)
+    }
+
+    #[inline(always)]
+    unsafe fn set(x: f32) -> Self {
+        f4(_mm_set1_ps(x))
+    }
+
+    #[inline(always)]
+    unsafe fn add(self, b: Self) -> Self {
+        f4(_mm_add_ps(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn sub(self, b: Self) -> Self {
+        f4(_mm_sub_ps(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn mul(self, b: Self) -> Self {
+        f4(_mm_mul_ps(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn rev(self) -> Self {
+        f4(_mm_shuffle_ps::<0x1b>(self.0, self.0))
+    }
+
+    #[inline(always)]
+    unsafe fn set_rows(p: *const f32, _: isize) -> Self {
+        Self::set(*p)
+    }
+
+    #[inline(always)]
+    unsafe fn st_rows(self, p: *mut f32, _: isize) {
+        self.st(p)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Pairs for f4 {
+    #[inline(always)]
+    unsafe fn ld2(p: *const f32) -> Self {
+        f4(_mm_castpd_ps(_mm_load_sd(p as *const f64)))
+    }
+
+    #[inline(always)]
+    unsafe fn ld2x2(p: *const f32, q: *const f32) -> Self {
+        f4(_mm_castpd_ps(_mm_loadh_pd(_mm_load_sd(p as *const f64), q as *const f64)))
+    }
+
+    #[inline(always)]
+    unsafe fn st2(self, p: *mut f32) {
+        _mm_store_sd(p as *mut f64, _mm_castps_pd(self.0))
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+impl Vf32 for f4 {
+    const LANES: usize = 4;
+
+    #[inline(always)]
+    unsafe fn ld(p: *const f32) -> Self {
+        f4(vld1q_f32(p))
+    }
+
+    #[inline(always)]
+    unsafe fn st(self, p: *mut f32) {
+        vst1q_f32(p, self.0)
+    }
+
+    #[inline(always)]
+    unsafe fn set(x: f32) -> Self {
+        f4(vdupq_n_f32(x))
+    }
+
+    #[inline(always)]
+    unsafe fn add(self, b: Self) -> Self {
+        f4(vaddq_f32(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn sub(self, b: Self) -> Self {
+        f4(vsubq_f32(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn mul(self, b: Self) -> Self {
+        f4(vmulq_f32(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn rev(self) -> Self {
+        let v = vrev64q_f32(self.0);
+        f4(vextq_f32::<2>(v, v))
+    }
+
+    #[inline(always)]
+    unsafe fn set_rows(p: *const f32, _: isize) -> Self {
+        Self::set(*p)
+    }
+
+    #[inline(always)]
+    unsafe fn st_rows(self, p: *mut f32, _: isize) {
+        self.st(p)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+impl Pairs for f4 {
+    #[inline(always)]
+    unsafe fn ld2(p: *const f32) -> Self {
+        f4(vcombine_f32(vld1_f32(p), vdup_n_f32(0.)))
+    }
+
+    #[inline(always)]
+    unsafe fn ld2x2(p: *const f32, q: *const f32) -> Self {
+        f4(vcombine_f32(vld1_f32(p), vld1_f32(q)))
+    }
+
+    #[inline(always)]
+    unsafe fn st2(self, p: *mut f32) {
+        vst1_f32(p, vget_low_f32(self.0))
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+impl Vf32 for f4 {
+    const LANES: usize = 4;
+
+    #[inline(always)]
+    unsafe fn ld(p: *const f32) -> Self {
+        f4(v128_load(p as *const v128))
+    }
+
+    #[inline(always)]
+    unsafe fn st(self, p: *mut f32) {
+        v128_store(p as *mut v128, self.0)
+    }
+
+    #[inline(always)]
+    unsafe fn set(x: f32) -> Self {
+        f4(f32x4_splat(x))
+    }
+
+    #[inline(always)]
+    unsafe fn add(self, b: Self) -> Self {
+        f4(f32x4_add(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn sub(self, b: Self) -> Self {
+        f4(f32x4_sub(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn mul(self, b: Self) -> Self {
+        f4(f32x4_mul(self.0, b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn rev(self) -> Self {
+        f4(i32x4_shuffle::<3, 2, 1, 0>(self.0, self.0))
+    }
+
+    #[inline(always)]
+    unsafe fn set_rows(p: *const f32, _: isize) -> Self {
+        Self::set(*p)
+    }
+
+    #[inline(always)]
+    unsafe fn st_rows(self, p: *mut f32, _: isize) {
+        self.st(p)
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+impl Pairs for f4 {
+    #[inline(always)]
+    unsafe fn ld2(p: *const f32) -> Self {
+        f4(v128_load64_zero(p as *const u64))
+    }
+
+    #[inline(always)]
+    unsafe fn ld2x2(p: *const f32, q: *const f32) -> Self {
+        f4(v128_load64_lane::<1>(v128_load64_zero(p as *const u64), q as *const u64))
+    }
+
+    #[inline(always)]
+    unsafe fn st2(self, p: *mut f32) {
+        v128_store64_lane::<0>(self.0, p as *mut u64)
+    }
+}
+
+/// Lane by lane, for the targets without vectors. [`ENABLED`] is false for them, so this only has to compile.
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    all(target_arch = "wasm32", target_feature = "simd128"),
+)))]
+impl Vf32 for f4 {
+    const LANES: usize = 4;
+
+    unsafe fn ld(p: *const f32) -> Self {
+        f4(*(p as *const [f32; 4]))
+    }
+
+    unsafe fn st(self, p: *mut f32) {
+        *(p as *mut [f32; 4]) = self.0
+    }
+
+    unsafe fn set(x: f32) -> Self {
+        f4([x; 4])
+    }
+
+    unsafe fn add(self, b: Self) -> Self {
+        f4(core::array::from_fn(|i| self.0[i] + b.0[i]))
+    }
+
+    unsafe fn sub(self, b: Self) -> Self {
+        f4(core::array::from_fn(|i| self.0[i] - b.0[i]))
+    }
+
+    unsafe fn mul(self, b: Self) -> Self {
+        f4(core::array::from_fn(|i| self.0[i] * b.0[i]))
+    }
+
+    unsafe fn rev(self) -> Self {
+        f4(core::array::from_fn(|i| self.0[3 - i]))
+    }
+
+    unsafe fn set_rows(p: *const f32, _: isize) -> Self {
+        Self::set(*p)
+    }
+
+    unsafe fn st_rows(self, p: *mut f32, _: isize) {
+        self.st(p)
+    }
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    all(target_arch = "wasm32", target_feature = "simd128"),
+)))]
+impl Pairs for f4 {
+    unsafe fn ld2(p: *const f32) -> Self {
+        f4([*p, *p.add(1), 0., 0.])
+    }
+
+    unsafe fn ld2x2(p: *const f32, q: *const f32) -> Self {
+        f4([*p, *p.add(1), *q, *q.add(1)])
+    }
+
+    unsafe fn st2(self, p: *mut f32) {
+        *p = self.0[0];
+        *p.add(1) = self.0[1];
+    }
+}
+
+/// The first two lanes of an [`f4`], for the last two columns of the DCT.
+#[derive(Copy, Clone)]
+struct f2(f4);
+
+impl Vf32 for f2 {
+    const LANES: usize = 2;
+
+    #[inline(always)]
+    unsafe fn ld(p: *const f32) -> Self {
+        f2(f4::ld2(p))
+    }
+
+    #[inline(always)]
+    unsafe fn st(self, p: *mut f32) {
+        self.0.st2(p)
+    }
+
+    #[inline(always)]
+    unsafe fn set(x: f32) -> Self {
+        f2(f4::set(x))
+    }
+
+    #[inline(always)]
+    unsafe fn add(self, b: Self) -> Self {
+        f2(self.0.add(b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn sub(self, b: Self) -> Self {
+        f2(self.0.sub(b.0))
+    }
+
+    #[inline(always)]
+    unsafe fn mul(self, b: Self) -> Self {
+        f2(self.0.mul(b.0))
+    }
+
+    /// Not needed by the DCT.
+    #[inline(always)]
+    unsafe fn rev(self) -> Self {
+        unreachable!()
+    }
+
+    #[inline(always)]
+    unsafe fn set_rows(p: *const f32, _: isize) -> Self {
+        Self::set(*p)
+    }
+
+    #[inline(always)]
+    unsafe fn st_rows(self, p: *mut f32, _: isize) {
+        self.st(p)
+    }
+}
+
+/// `mp3d_DCT_II` on `V::LANES` adjacent columns.
+#[inline(always)]
+unsafe fn DCT_II_columns<V: Vf32>(y: *mut f32) {
+    let sec = MP3D_DCT_II_G_SEC.as_ptr() as *const f32;
+    let mut t = [[V::set(0.); 8]; 4];
+    for i in 0..8 {
+        let x0 = V::ld(y.add(i * 18));
+        let x1 = V::ld(y.add((15 - i) * 18));
+        let x2 = V::ld(y.add((16 + i) * 18));
+        let x3 = V::ld(y.add((31 - i) * 18));
+        let t0 = x0.add(x3);
+        let t1 = x1.add(x2);
+        let t2 = x1.sub(x2).mul(V::set(*sec.add(3 * i)));
+        let t3 = x0.sub(x3).mul(V::set(*sec.add(3 * i + 1)));
+        t[0][i] = t0.add(t1);
+        t[1][i] = t0.sub(t1).mul(V::set(*sec.add(3 * i + 2)));
+        t[2][i] = t3.add(t2);
+        t[3][i] = t3.sub(t2).mul(V::set(*sec.add(3 * i + 2)));
+    }
+    for x in &mut t {
+        let [mut x0, mut x1, mut x2, mut x3, mut x4, mut x5, mut x6, mut x7] = *x;
+        let mut xt = x0.sub(x7);
+        x0 = x0.add(x7);
+        x7 = x1.sub(x6);
+        x1 = x1.add(x6);
+        x6 = x2.sub(x5);
+        x2 = x2.add(x5);
+        x5 = x3.sub(x4);
+        x3 = x3.add(x4);
+        x4 = x0.sub(x3);
+        x0 = x0.add(x3);
+        x3 = x1.sub(x2);
+        x1 = x1.add(x2);
+        x[0] = x0.add(x1);
+        x[4] = x0.sub(x1).mul(V::set(0.70710677));
+        x5 = x5.add(x6);
+        x6 = x6.add(x7).mul(V::set(0.70710677));
+        x7 = x7.add(xt);
+        x3 = x3.add(x4).mul(V::set(0.70710677));
+        x5 = x5.sub(x7.mul(V::set(0.198912367)));
+        x7 = x7.add(x5.mul(V::set(0.382683432)));
+        x5 = x5.sub(x7.mul(V::set(0.198912367)));
+        x0 = xt.sub(x6);
+        xt = xt.add(x6);
+        x[1] = xt.add(x7).mul(V::set(0.50979561));
+        x[2] = x4.add(x3).mul(V::set(0.54119611));
+        x[3] = x0.sub(x5).mul(V::set(0.60134488));
+        x[5] = x0.add(x5).mul(V::set(0.89997619));
+        x[6] = x4.sub(x3).mul(V::set(1.30656302));
+        x[7] = xt.sub(x7).mul(V::set(2.56291556));
+    }
+    let mut y = y;
+    for i in 0..7 {
+        t[0][i].st(y);
+        t[2][i].add(t[3][i]).add(t[3][i + 1]).st(y.add(18));
+        t[1][i].add(t[1][i + 1]).st(y.add(2 * 18));
+        t[2][i + 1].add(t[3][i]).add(t[3][i + 1]).st(y.add(3 * 18));
+        y = y.add(4 * 18);
+    }
+    t[0][7].st(y);
+    t[2][7].add(t[3][7]).st(y.add(18));
+    t[1][7].st(y.add(2 * 18));
+    t[3][7].st(y.add(3 * 18));
+}
+
+#[inline(always)]
+unsafe fn DCT_II_with<V: Vf32>(grbuf: *mut f32, n: u32) -> u32 {
+    let n = n as usize;
+    let mut k = 0;
+    while k + V::LANES <= n {
+        DCT_II_columns::<V>(grbuf.add(k));
+        k += V::LANES;
+    }
+    while k + 4 <= n {
+        DCT_II_columns::<f4>(grbuf.add(k));
+        k += 4;
+    }
+    if k + 2 <= n {
+        DCT_II_columns::<f2>(grbuf.add(k));
+        k += 2;
+    }
+    k as u32
+}
+
+pub(crate) unsafe fn DCT_II_f4(grbuf: *mut f32, n: u32) -> u32 {
+    DCT_II_with::<f4>(grbuf, n)
+}
+
+/// `x` as `f32`s. The entry points assert [`ENABLED`] first, which only holds for the `f32` pipeline.
+#[inline(always)]
+fn f32s(x: &mut [mp3d_real_t]) -> *mut f32 {
+    x.as_mut_ptr() as *mut f32
+}
+
+/// Runs `mp3d_DCT_II` on the columns of `grbuf` up to the last pair, returning how many are done.
+#[inline(always)]
+pub(crate) fn DCT_II(grbuf: &mut [mp3d_real_t], n: u32) -> u32 {
+    assert!(ENABLED && n <= 18);
+    let grbuf = f32s(&mut grbuf[..576]);
+    #[cfg(target_arch = "x86_64")]
+    if x86::have_avx2() {
+        return unsafe { x86::DCT_II_avx2(grbuf, n) };
+    }
+    unsafe { DCT_II_f4(grbuf, n) }
+}
+
+#[inline(always)]
+unsafe fn imdct36_window_with<V: Vf32>(
+    grbuf: *mut f32,
+    overlap: *mut f32,
+    co: *const f32,
+    si: *const f32,
+    window: *const f32,
+) {
+    let twid9 = L3_IMDCT36_G_TWID9.as_ptr() as *const f32;
+    let mut i = 0;
+    while i < 8 {
+        let ovl = V::ld(overlap.add(i));
+        let c = V::ld(co.add(i));
+        let s = V::ld(si.add(i));
+        let r0 = V::ld(twid9.add(i));
+        let r1 = V::ld(twid9.add(9 + i));
+        let w0 = V::ld(window.add(i));
+        let w1 = V::ld(window.add(9 + i));
+        let sum = c.mul(r1).add(s.mul(r0));
+        c.mul(r0).sub(s.mul(r1)).st(overlap.add(i));
+        ovl.mul(w0).sub(sum.mul(w1)).st(grbuf.add(i));
+        ovl.mul(w1).add(sum.mul(w0)).rev().st(grbuf.add(18 - V::LANES - i));
+        i += V::LANES;
+    }
+}
+
+pub(crate) unsafe fn imdct36_window_f4(
+    grbuf: *mut f32,
+    overlap: *mut f32,
+    co: *const f32,
+    si: *const f32,
+    window: *const f32,
+) {
+    imdct36_window_with::<f4>(grbuf, overlap, co, si, window)
+}
+
+/// The overlap-add and windowing at the end of `L3_imdct36`, for the first 8 of the 9 lines.
+#[inline(always)]
+pub(crate) fn imdct36_window(
+    grbuf: &mut [mp3d_real_t],
+    overlap: &mut [mp3d_real_t],
+    co: &[mp3d_real_t; 9],
+    si: &[mp3d_real_t; 9],
+    window: &[mp3d_real_t; 18],
+) {
+    assert!(ENABLED);
+    let grbuf = f32s(&mut grbuf[..18]);
+    let overlap = f32s(&mut overlap[..9]);
+    let (co, si, window) = (co.as_ptr() as *const f32, si.as_ptr() as *const f32, window.as_ptr() as *const f32);
+    #[cfg(target_arch = "x86_64")]
+    if x86::have_avx2() {
+        return unsafe { x86::imdct36_window_avx2(grbuf, overlap, co, si, window) };
+    }
+    unsafe { imdct36_window_f4(grbuf, overlap, co, si, window) }
+}
+
+/// The sums `a` and `b` of `mp3d_synth` for row `i`, and with 8 lanes for row `i + 1` as well.
+#[inline(always)]
+unsafe fn synth_rows<V: Vf32>(zlin: *const f32, i: usize, acc: *mut f32) {
+    let w = (MP3D_SYNTH_G_WIN.as_ptr() as *const f32).add((14 - i) * 16);
+    let zlin = zlin.add(4 * i);
+    let mut a = V::set(0.);
+    let mut b = V::set(0.);
+    for m in 0..8 {
+        let w0 = V::set_rows(w.add(2 * m), -16);
+        let w1 = V::set_rows(w.add(2 * m + 1), -16);
+        let vz = V::ld(zlin.sub(m * 64));
+        let vy = V::ld(zlin.sub((15 - m) * 64));
+        let bm = vz.mul(w1).add(vy.mul(w0));
+        let am = if m % 2 == 0 {
+            vz.mul(w0).sub(vy.mul(w1))
+        } else {
+            vy.mul(w1).sub(vz.mul(w0))
+        };
+        if m == 0 {
+            b = bm;
+            a = am;
+        } else {
+            b = b.add(bm);
+            a = a.add(am);
+        }
+    }
+    a.st_rows(acc.add(8 * i), 8);
+    b.st_rows(acc.add(8 * i + 4), 8);
+}
+
+#[inline(always)]
+unsafe fn synth_with<V: Vf32>(xl: *const f32, xr: *const f32, zlin: *mut f32, acc: *mut f32) {
+    for i in 0..15 {
+        *zlin.add(4 * i) = *xl.add(18 * (31 - i));
+        *zlin.add(4 * i + 1) = *xr.add(18 * (31 - i));
+        *zlin.add(4 * i + 2) = *xl.add(1 + 18 * (31 - i));
+        *zlin.add(4 * i + 3) = *xr.add(1 + 18 * (31 - i));
+        *zlin.add(4 * (i + 16)) = *xl.add(1 + 18 * (1 + i));
+        *zlin.add(4 * (i + 16) + 1) = *xr.add(1 + 18 * (1 + i));
+        *zlin.add(4 * i + 2).sub(4 * 16) = *xl.add(18 * (1 + i));
+        *zlin.add(4 * i + 3).sub(4 * 16) = *xr.add(18 * (1 + i));
+    }
+    let rows = V::LANES / 4;
+    let mut i = 0;
+    while i + rows <= 15 {
+        synth_rows::<V>(zlin, i, acc);
+        i += rows;
+    }
+    if i < 15 {
+        synth_rows::<f4>(zlin, i, acc);
+    }
+}
+
+pub(crate) unsafe fn synth_f4(xl: *const f32, xr: *const f32, zlin: *mut f32, acc: *mut f32) {
+    synth_with::<f4>(xl, xr, zlin, acc)
+}
+
+/// The loop over rows 14 to 0 of `mp3d_synth`. Fills in `zlin`, at row 15 of `lins`, and stores `a` then `b` of
+/// row `i` to `acc[i]`.
+#[inline(always)]
+pub(crate) fn synth(xl: &[mp3d_real_t], xr: &[mp3d_real_t], lins: &mut [mp3d_real_t], acc: &mut [[mp3d_acc_t; 8]; 15]) {
+    assert!(ENABLED);
+    let (xl, xr) = (xl[..560].as_ptr() as *const f32, xr[..560].as_ptr() as *const f32);
+    let zlin = unsafe { f32s(&mut lins[..15 * 64 + 128]).add(15 * 64) };
+    let acc = acc.as_flattened_mut().as_mut_ptr() as *mut f32;
+    #[cfg(target_arch = "x86_64")]
+    if x86::have_avx2() {
+        return unsafe { x86::synth_avx2(xl, xr, zlin, acc) };
+    }
+    unsafe { synth_f4(xl, xr, zlin, acc) }
+}
+
+/// The four calls to `mp3d_synth_pair` at the start of `mp3d_synth`, one to a lane, on rows 0 to 15 of `lins` from
+/// column 60. Stores the first sum of each call to `acc[..4]` and the second to `acc[4..]`, for the left channel and
+/// the right of the first sample, then of the next.
+#[inline(always)]
+pub(crate) fn synth_pair(lins: &[mp3d_real_t], acc: &mut [mp3d_acc_t; 8]) {
+    assert!(ENABLED);
+    let z = lins[4 * 15..16 * 64].as_ptr() as *const f32;
+    let acc = acc.as_mut_ptr() as *mut f32;
+    unsafe { synth_pair_f4(z, acc) }
+}
+
+#[inline(always)]
+unsafe fn synth_pair_f4(z: *const f32, acc: *mut f32) {
+    let ld = |row: usize, column: usize| f4::ld2x2(z.add(row * 64 + column), z.add((row + 1) * 64 + column));
+    let w = |x: f32| f4::set(x);
+    let mut a = ld(14, 0).sub(ld(0, 0)).mul(w(29.));
+    a = a.add(ld(1, 0).add(ld(13, 0)).mul(w(213.)));
+    a = a.add(ld(12, 0).sub(ld(2, 0)).mul(w(459.)));
+    a = a.add(ld(3, 0).add(ld(11, 0)).mul(w(2037.)));
+    a = a.add(ld(10, 0).sub(ld(4, 0)).mul(w(5153.)));
+    a = a.add(ld(5, 0).add(ld(9, 0)).mul(w(6574.)));
+    a = a.add(ld(8, 0).sub(ld(6, 0)).mul(w(37489.)));
+    a = a.add(ld(7, 0).mul(w(75038.)));
+    a.st(acc);
+    let mut b = ld(14, 2).mul(w(104.));
+    b = b.add(ld(12, 2).mul(w(1567.)));
+    b = b.add(ld(10, 2).mul(w(9727.)));
+    b = b.add(ld(8, 2).mul(w(64019.)));
+    b = b.add(ld(6, 2).mul(w(-9975.)));
+    b = b.add(ld(4, 2).mul(w(-45.)));
+    b = b.add(ld(2, 2).mul(w(146.)));
+    b = b.add(ld(0, 2).mul(w(-5.)));
+    b.st(acc.add(4));
+}
+
+/// `L3_antialias` on one band.
+#[inline(always)]
+pub(crate) fn antialias(grbuf: &mut [mp3d_real_t]) {
+    assert!(ENABLED);
+    unsafe { antialias_f4(f32s(&mut grbuf[..26])) }
+}
+
+#[inline(always)]
+unsafe fn antialias_f4(grbuf: *mut f32) {
+    let aa = L3_ANTIALIAS_G_AA.as_ptr() as *const f32;
+    let mut i = 0;
+    while i < 8 {
+        let u = f4::ld(grbuf.add(18 + i));
+        let d = f4::ld(grbuf.add(14 - i)).rev();
+        let c0 = f4::ld(aa.add(i));
+        let c1 = f4::ld(aa.add(8 + i));
+        u.mul(c0).sub(d.mul(c1)).st(grbuf.add(18 + i));
+        u.mul(c1).add(d.mul(c0)).rev().st(grbuf.add(14 - i));
+        i += 4;
+    }
+}
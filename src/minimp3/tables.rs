@@ -1,3 +1,5 @@
+use super::*;
+
 pub static HDR_BITRATE_KBPS_HALFRATE: [[[u8; 15]; 3]; 2] = [
     [
         [
@@ -1140,7 +1142,7 @@ pub static L3_DECODE_SCALEFACTORS_G_PREAMP: [u8; 10] = [
     2,
 ];
 
-pub static G_POW43: [f32; 145] = [
+pub static G_POW43: [mp3d_pow43_t; 145] = pow43_table([
     0f32,
     -1f32,
     -2.519842f32,
@@ -1286,7 +1288,7 @@ pub static G_POW43: [f32; 145] = [
     631.675540f32,
     638.368763f32,
     645.079578f32,
-];
+]);
 
 pub static L3_HUFFMAN_TABS: [i16; 2164] = [
     0,
@@ -3575,7 +3577,7 @@ pub static L3_HUFFMAN_G_LINBITS: [u8; 32] = [
     13,
 ];
 
-pub static L3_STEREO_PROCESS_G_PAN: [f32; 14] = [
+pub static L3_STEREO_PROCESS_G_PAN: [mp3d_real_t; 14] = real_table([
     0f32,
     1f32,
     0.21132487f32,
@@ -3590,10 +3592,10 @@ pub static L3_STEREO_PROCESS_G_PAN: [f32; 14] = [
     0.21132487f32,
     1f32,
     0f32,
-];
+]);
 
-pub static L3_ANTIALIAS_G_AA: [[f32; 8]; 2] = [
-    [
+pub static L3_ANTIALIAS_G_AA: [[mp3d_real_t; 8]; 2] = [
+    real_table([
         0.85749293f32,
         0.88174200f32,
         0.94962865f32,
@@ -3602,8 +3604,8 @@ pub static L3_ANTIALIAS_G_AA: [[f32; 8]; 2] = [
         0.99916056f32,
         0.99989920f32,
         0.99999316f32,
-    ],
-    [
+    ]),
+    real_table([
         0.51449576f32,
         0.47173197f32,
         0.31337745f32,
@@ -3612,10 +3614,10 @@ pub static L3_ANTIALIAS_G_AA: [[f32; 8]; 2] = [
         0.04096558f32,
         0.01419856f32,
         0.00369997f32,
-    ],
+    ]),
 ];
 
-pub static L3_IMDCT36_G_TWID9: [f32; 18] = [
+pub static L3_IMDCT36_G_TWID9: [mp3d_real_t; 18] = real_table([
     0.73727734f32,
     0.79335334f32,
     0.84339145f32,
@@ -3634,10 +3636,10 @@ pub static L3_IMDCT36_G_TWID9: [f32; 18] = [
     0.21643961f32,
     0.13052619f32,
     0.04361938f32,
-];
+]);
 
-pub static L3_IMDCT_GR_G_MDCT_WINDOW: [[f32; 18]; 2] = [
-    [
+pub static L3_IMDCT_GR_G_MDCT_WINDOW: [[mp3d_real_t; 18]; 2] = [
+    real_table([
         0.99904822f32,
         0.99144486f32,
         0.97629601f32,
@@ -3656,8 +3658,8 @@ pub static L3_IMDCT_GR_G_MDCT_WINDOW: [[f32; 18]; 2] = [
         0.53729961f32,
         0.60876143f32,
         0.67559021f32,
-    ],
-    [
+    ]),
+    real_table([
         1f32,
         1f32,
         1f32,
@@ -3676,10 +3678,10 @@ pub static L3_IMDCT_GR_G_MDCT_WINDOW: [[f32; 18]; 2] = [
         0.13052619f32,
         0.38268343f32,
         0.60876143f32,
-    ],
+    ]),
 ];
 
-pub static MP3D_DCT_II_G_SEC: [f32; 24] = [
+pub static MP3D_DCT_II_G_SEC: [mp3d_real_t; 24] = real_table([
     10.19000816f32,
     0.50060302f32,
     0.50241929f32,
@@ -3704,9 +3706,9 @@ pub static MP3D_DCT_II_G_SEC: [f32; 24] = [
     0.74453628f32,
     0.67480832f32,
     5.10114861f32,
-];
+]);
 
-pub static MP3D_SYNTH_G_WIN: [f32; 240] = [
+pub static MP3D_SYNTH_G_WIN: [mp3d_win_t; 240] = win_table([
     -1f32,
     26f32,
     -31f32,
@@ -3947,26 +3949,193 @@ pub static MP3D_SYNTH_G_WIN: [f32; 240] = [
     11455f32,
     -62684f32,
     65290f32,
-];
+]);
 
-pub static L3_LDEXP_Q2_G_EXPFRAC: [f32; 4] = [
-    9.31322575e-10f32,
-    7.83145814e-10f32,
-    6.58544508e-10f32,
-    5.53767716e-10f32,
-];
-
-pub static L3_IMDCT12_G_TWID3: [f32; 6] = [
+pub static L3_IMDCT12_G_TWID3: [mp3d_real_t; 6] = real_table([
     0.79335334f32,
     0.92387953f32,
     0.99144486f32,
     0.60876143f32,
     0.38268343f32,
     0.13052619f32,
-];
+]);
 
 pub static HDR_SAMPLE_RATE_HZ_G_HZ: [u32; 3] = [
     44100,
     48000,
     32000,
-];
\ No newline at end of file
+];
+
+pub static L12_READ_SCALEFACTORS_G_DEQ_L12: [mp3d_gain_t; 54] = gain_table([
+    3.17891448e-07f32,
+    2.52310599e-07f32,
+    2.00259066e-07f32,
+    1.36239194e-07f32,
+    1.08133115e-07f32,
+    8.58253131e-08f32,
+    6.35782911e-08f32,
+    5.04621198e-08f32,
+    4.00518125e-08f32,
+    3.07636867e-08f32,
+    2.44171545e-08f32,
+    1.93799092e-08f32,
+    1.51376884e-08f32,
+    1.20147901e-08f32,
+    9.53614610e-09f32,
+    7.50924656e-09f32,
+    5.96009286e-09f32,
+    4.73052886e-09f32,
+    3.73989950e-09f32,
+    2.96836000e-09f32,
+    2.35598896e-09f32,
+    1.86629023e-09f32,
+    1.48127555e-09f32,
+    1.17568921e-09f32,
+    9.32232957e-10f32,
+    7.39913797e-10f32,
+    5.87269955e-10f32,
+    4.65888772e-10f32,
+    3.69776154e-10f32,
+    2.93491537e-10f32,
+    2.32887515e-10f32,
+    1.84842933e-10f32,
+    1.46709936e-10f32,
+    1.16429533e-10f32,
+    9.24101837e-11f32,
+    7.33460098e-11f32,
+    5.82112136e-11f32,
+    4.62022712e-11f32,
+    3.66707671e-11f32,
+    2.91047186e-11f32,
+    2.31004296e-11f32,
+    1.83348250e-11f32,
+    1.45521373e-11f32,
+    1.15500387e-11f32,
+    9.16727198e-12f32,
+    3.17891448e-07f32,
+    2.52310599e-07f32,
+    2.00259066e-07f32,
+    1.90734866e-07f32,
+    1.51386359e-07f32,
+    1.20155434e-07f32,
+    1.05963814e-07f32,
+    8.41035330e-08f32,
+    6.67530173e-08f32,
+]);
+
+pub static L12_READ_SCALE_INFO_G_BITALLOC_CODE_TAB: [u8; 92] = [
+    0,
+    17,
+    3,
+    4,
+    5,
+    6,
+    7,
+    8,
+    9,
+    10,
+    11,
+    12,
+    13,
+    14,
+    15,
+    16,
+    0,
+    17,
+    18,
+    3,
+    19,
+    4,
+    5,
+    6,
+    7,
+    8,
+    9,
+    10,
+    11,
+    12,
+    13,
+    16,
+    0,
+    17,
+    18,
+    3,
+    19,
+    4,
+    5,
+    16,
+    0,
+    17,
+    18,
+    16,
+    0,
+    17,
+    18,
+    19,
+    4,
+    5,
+    6,
+    7,
+    8,
+    9,
+    10,
+    11,
+    12,
+    13,
+    14,
+    15,
+    0,
+    17,
+    18,
+    3,
+    19,
+    4,
+    5,
+    6,
+    7,
+    8,
+    9,
+    10,
+    11,
+    12,
+    13,
+    14,
+    0,
+    2,
+    3,
+    4,
+    5,
+    6,
+    7,
+    8,
+    9,
+    10,
+    11,
+    12,
+    13,
+    14,
+    15,
+    16,
+];
+
+pub static L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L1: [[u8; 3]; 1] = [
+    [76, 4, 32],
+];
+
+pub static L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L2M2: [[u8; 3]; 3] = [
+    [60, 4, 4],
+    [44, 3, 7],
+    [44, 2, 19],
+];
+
+pub static L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L2M1: [[u8; 3]; 4] = [
+    [0, 4, 3],
+    [16, 4, 8],
+    [32, 3, 12],
+    [40, 2, 7],
+];
+
+pub static L12_SUBBAND_ALLOC_TABLE_G_ALLOC_L2M1_LOWRATE: [[u8; 3]; 2] = [
+    [44, 4, 2],
+    [44, 3, 10],
+];
@@ -0,0 +1,92 @@
+//! The kernels of [`super`] over the 8 lanes of AVX2, where they have room for them.
+//!
+//! AVX2 is detected at runtime with the `std` feature, and otherwise used when enabled at compile time, such as with
+//! `-C target-feature=+avx2`.
+
+use core::arch::x86_64::*;
+
+use super::*;
+
+/// Whether the AVX2 kernels can be used.
+#[inline(always)]
+pub(crate) fn have_avx2() -> bool {
+    #[cfg(feature = "std")]
+    {
+        std::is_x86_feature_detected!("avx2")
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        cfg!(target_feature = "avx2")
+    }
+}
+
+impl Vf32 for __m256 {
+    const LANES: usize = 8;
+
+    #[inline(always)]
+    unsafe fn ld(p: *const f32) -> Self {
+        _mm256_loadu_ps(p)
+    }
+
+    #[inline(always)]
+    unsafe fn st(self, p: *mut f32) {
+        _mm256_storeu_ps(p, self)
+    }
+
+    #[inline(always)]
+    unsafe fn set(x: f32) -> Self {
+        _mm256_set1_ps(x)
+    }
+
+    #[inline(always)]
+    unsafe fn add(self, b: Self) -> Self {
+        _mm256_add_ps(self, b)
+    }
+
+    #[inline(always)]
+    unsafe fn sub(self, b: Self) -> Self {
+        _mm256_sub_ps(self, b)
+    }
+
+    #[inline(always)]
+    unsafe fn mul(self, b: Self) -> Self {
+        _mm256_mul_ps(self, b)
+    }
+
+    #[inline(always)]
+    unsafe fn rev(self) -> Self {
+        _mm256_permutevar8x32_ps(self, _mm256_setr_epi32(7, 6, 5, 4, 3, 2, 1, 0))
+    }
+
+    #[inline(always)]
+    unsafe fn set_rows(p: *const f32, stride: isize) -> Self {
+        _mm256_set_m128(_mm_set1_ps(*p.offset(stride)), _mm_set1_ps(*p))
+    }
+
+    #[inline(always)]
+    unsafe fn st_rows(self, p: *mut f32, stride: isize) {
+        _mm_storeu_ps(p, _mm256_castps256_ps128(self));
+        _mm_storeu_ps(p.offset(stride), _mm256_extractf128_ps::<1>(self));
+    }
+}
+
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn DCT_II_avx2(grbuf: *mut f32, n: u32) -> u32 {
+    DCT_II_with::<__m256>(grbuf, n)
+}
+
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn imdct36_window_avx2(
+    grbuf: *mut f32,
+    overlap: *mut f32,
+    co: *const f32,
+    si: *const f32,
+    window: *const f32,
+) {
+    imdct36_window_with::<__m256>(grbuf, overlap, co, si, window)
+}
+
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn synth_avx2(xl: *const f32, xr: *const f32, zlin: *mut f32, acc: *mut f32) {
+    synth_with::<__m256>(xl, xr, zlin, acc)
+}
@@ -0,0 +1,63 @@
+use core::ops::Range;
+
+use crate::Sample;
+
+/// Where the synthesis filterbank writes the samples of a frame, in the layout the caller asked for.
+pub(crate) trait Pcm {
+    /// The format samples are converted to as they leave the filterbank.
+    type Sample: Sample;
+
+    /// Writes sample `k` of channel `ch`.
+    fn set(&mut self, nch: u32, ch: u32, k: usize, sample: Self::Sample);
+
+    /// Fills the first `samples` samples of each channel with silence.
+    fn silence(&mut self, nch: usize, samples: usize);
+
+    /// Moves the samples in `range` of each channel to the start.
+    fn keep(&mut self, nch: usize, range: Range<usize>);
+}
+
+/// Samples of each channel in turn, as one slice.
+pub(crate) struct Interleaved<'a, S>(pub &'a mut [S]);
+
+impl<S: Sample> Pcm for Interleaved<'_, S> {
+    type Sample = S;
+
+    #[inline(always)]
+    fn set(&mut self, nch: u32, ch: u32, k: usize, sample: S) {
+        self.0[k * nch as usize + ch as usize] = sample;
+    }
+
+    fn silence(&mut self, nch: usize, samples: usize) {
+        self.0[..samples * nch].fill(S::default());
+    }
+
+    fn keep(&mut self, nch: usize, range: Range<usize>) {
+        self.0.copy_within(range.start * nch..range.end * nch, 0);
+    }
+}
+
+/// The left and right channels in separate slices. Mono streams are written to the left channel only.
+pub(crate) struct Planar<'a, S>(pub &'a mut [S], pub &'a mut [S]);
+
+impl<S: Sample> Pcm for Planar<'_, S> {
+    type Sample = S;
+
+    #[inline(always)]
+    fn set(&mut self, _nch: u32, ch: u32, k: usize, sample: S) {
+        let channel = if ch == 0 { &mut *self.0 } else { &mut *self.1 };
+        channel[k] = sample;
+    }
+
+    fn silence(&mut self, nch: usize, samples: usize) {
+        for channel in [&mut *self.0, &mut *self.1].into_iter().take(nch) {
+            channel[..samples].fill(S::default());
+        }
+    }
+
+    fn keep(&mut self, nch: usize, range: Range<usize>) {
+        for channel in [&mut *self.0, &mut *self.1].into_iter().take(nch) {
+            channel.copy_within(range.clone(), 0);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(feature = "fixed-point")]
+use crate::real::FRAC_BITS;
+
+/// A PCM sample format that [`crate::Decoder`] can decode to. The conversion is done as the samples leave the
+/// synthesis filterbank, so there is no second pass over the decoded frame.
+///
+/// This trait is sealed, and implemented for:
+/// - `f32` and `f64`, in the range -1.0 to 1.0
+/// - `i16` and `i32`, at full scale
+/// - [`I24`], packed 24-bit samples at full scale
+///
+/// Integer samples are rounded and saturated.
+pub trait Sample: sealed::Sealed {}
+
+pub(crate) mod sealed {
+    pub trait Sealed: Copy + Default {
+        /// Converts a sample from the filterbank, at the scale of 16-bit PCM.
+        fn scale_pcm(sample: f32) -> Self;
+
+        /// Converts a sample from the fixed-point filterbank, at the scale of 16-bit PCM with
+        /// [`FRAC_BITS`](crate::real::FRAC_BITS) fractional bits.
+        #[cfg(feature = "fixed-point")]
+        fn scale_fixed(sample: i64) -> Self;
+    }
+}
+
+/// A signed 24-bit sample, packed into 3 little-endian bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(transparent)]
+pub struct I24([u8; 3]);
+
+impl I24 {
+    /// The largest 24-bit sample.
+    pub const MAX: I24 = I24([0xff, 0xff, 0x7f]);
+    /// The smallest 24-bit sample.
+    pub const MIN: I24 = I24([0x00, 0x00, 0x80]);
+
+    /// Creates a sample from the low 24 bits of `value`.
+    pub const fn from_i32_wrapping(value: i32) -> Self {
+        let [a, b, c, _] = value.to_le_bytes();
+        Self([a, b, c])
+    }
+
+    /// Returns the sample sign-extended to 32 bits.
+    pub const fn to_i32(self) -> i32 {
+        let [a, b, c] = self.0;
+        i32::from_le_bytes([0, a, b, c]) >> 8
+    }
+
+    /// Creates a sample from its little-endian bytes.
+    pub const fn from_le_bytes(bytes: [u8; 3]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the little-endian bytes of the sample.
+    pub const fn to_le_bytes(self) -> [u8; 3] {
+        self.0
+    }
+}
+
+impl From<I24> for i32 {
+    fn from(sample: I24) -> i32 {
+        sample.to_i32()
+    }
+}
+
+/// Rounds half away from zero, saturating at the bounds of `i32`. Done in `f64`, as adding a half to a large `f32`
+/// would round it again.
+fn round(sample: f64) -> i32 {
+    (if sample < 0. { sample - 0.5 } else { sample + 0.5 }) as i32
+}
+
+/// Shifts a fixed-point sample right by `shift` bits, rounding and saturating at the bounds of `min..=max`.
+#[cfg(feature = "fixed-point")]
+fn round_shr(sample: i64, shift: u32, min: i32, max: i32) -> i32 {
+    ((sample + (1 << (shift - 1))) >> shift).clamp(min.into(), max.into()) as i32
+}
+
+impl Sample for f32 {}
+
+impl sealed::Sealed for f32 {
+    fn scale_pcm(sample: f32) -> f32 {
+        sample * (1f32/32768f32)
+    }
+
+    #[cfg(feature = "fixed-point")]
+    fn scale_fixed(sample: i64) -> f32 {
+        sample as f32 * (1f32/(1u64 << (FRAC_BITS + 15)) as f32)
+    }
+}
+
+impl Sample for f64 {}
+
+impl sealed::Sealed for f64 {
+    fn scale_pcm(sample: f32) -> f64 {
+        f64::from(sample) * (1f64/32768f64)
+    }
+
+    #[cfg(feature = "fixed-point")]
+    fn scale_fixed(sample: i64) -> f64 {
+        sample as f64 * (1f64/(1u64 << (FRAC_BITS + 15)) as f64)
+    }
+}
+
+impl Sample for i16 {}
+
+impl sealed::Sealed for i16 {
+    // As upstream minimp3 does without MINIMP3_FLOAT_OUTPUT
+    fn scale_pcm(sample: f32) -> i16 {
+        if sample >= 32766.5 {
+            return 32767;
+        }
+        if sample <= -32767.5 {
+            return -32768;
+        }
+        let s = (sample + 0.5) as i16;
+        // Round away from zero, to be compliant
+        s - (s < 0) as i16
+    }
+
+    #[cfg(feature = "fixed-point")]
+    fn scale_fixed(sample: i64) -> i16 {
+        round_shr(sample, FRAC_BITS, i16::MIN.into(), i16::MAX.into()) as i16
+    }
+}
+
+impl Sample for i32 {}
+
+impl sealed::Sealed for i32 {
+    fn scale_pcm(sample: f32) -> i32 {
+        round(f64::from(sample) * 65536.)
+    }
+
+    #[cfg(feature = "fixed-point")]
+    fn scale_fixed(sample: i64) -> i32 {
+        round_shr(sample, FRAC_BITS - 16, i32::MIN, i32::MAX)
+    }
+}
+
+impl Sample for I24 {}
+
+impl sealed::Sealed for I24 {
+    fn scale_pcm(sample: f32) -> I24 {
+        I24::from_i32_wrapping(round(f64::from(sample) * 256.).clamp(I24::MIN.to_i32(), I24::MAX.to_i32()))
+    }
+
+    #[cfg(feature = "fixed-point")]
+    fn scale_fixed(sample: i64) -> I24 {
+        I24::from_i32_wrapping(round_shr(sample, FRAC_BITS - 8, I24::MIN.to_i32(), I24::MAX.to_i32()))
+    }
+}
@@ -0,0 +1,141 @@
+use std::{io::{self, Read, Seek, SeekFrom}, time::Duration, vec, vec::Vec};
+
+use crate::{Decoder, FrameInfo, MAX_SAMPLES_PER_FRAME};
+
+/// The least MP3 data to have buffered before decoding a frame, so that the decoder can find the frames that follow.
+const MIN_BUFFER_SIZE: usize = 16384;
+
+/// A decoder that reads an MP3 stream from a [`Read`], managing the read-ahead buffer that [`Decoder`] needs.
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// let file = std::fs::File::open("march.mp3")?;
+/// let mut stream = nanomp3::StreamDecoder::new(file);
+/// while let Some((info, pcm)) = stream.next_frame()? {
+///     // `pcm` holds `info.samples_produced` interleaved samples for each channel
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamDecoder<R> {
+    reader: R,
+    decoder: Decoder,
+    buffer: Vec<u8>,
+    bottom: usize,
+    top: usize,
+    eof: bool,
+    pcm: Vec<f32>
+}
+
+impl<R: Read> StreamDecoder<R> {
+    /// Creates a `StreamDecoder` reading from the start of an MP3 stream.
+    pub fn new(reader: R) -> Self {
+        Self::with_decoder(reader, Decoder::new())
+    }
+
+    /// Creates a `StreamDecoder` with a decoder that has already been configured, e.g. with [`Decoder::set_gapless`].
+    pub fn with_decoder(reader: R, decoder: Decoder) -> Self {
+        Self {
+            reader,
+            decoder,
+            buffer: vec![0; 2 * MIN_BUFFER_SIZE],
+            bottom: 0,
+            top: 0,
+            eof: false,
+            pcm: vec![0.; MAX_SAMPLES_PER_FRAME]
+        }
+    }
+
+    /// Returns the underlying decoder, e.g. to read [`Decoder::vbr_info`].
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+
+    /// Returns the underlying decoder mutably, e.g. to change its [`crate::CrcPolicy`].
+    pub fn decoder_mut(&mut self) -> &mut Decoder {
+        &mut self.decoder
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Decodes the next frame that produces samples, returning info about it and its interleaved samples,
+    /// or `None` at the end of the stream.
+    ///
+    /// Junk, metadata and frames that can't be decoded are skipped over, as with [`Decoder::decode`].
+    pub fn next_frame(&mut self) -> io::Result<Option<(FrameInfo, &[f32])>> {
+        let info = loop {
+            if !self.eof && self.top - self.bottom < MIN_BUFFER_SIZE {
+                self.fill()?;
+            }
+            if self.top == self.bottom {
+                return Ok(None);
+            }
+            let (consumed, result) = self.decoder.try_decode(&self.buffer[self.bottom..self.top], &mut self.pcm);
+            self.bottom += consumed;
+            if let Ok(info) = result {
+                if info.samples_produced != 0 {
+                    break info;
+                }
+            }
+        };
+        let n = info.samples_produced * usize::from(info.channels.num());
+        Ok(Some((info, &self.pcm[..n])))
+    }
+
+    /// Reads until the buffer holds at least `MIN_BUFFER_SIZE` bytes, or the end of the stream is reached.
+    fn fill(&mut self) -> io::Result<()> {
+        self.buffer.copy_within(self.bottom..self.top, 0);
+        self.top -= self.bottom;
+        self.bottom = 0;
+        while self.top < MIN_BUFFER_SIZE {
+            match self.reader.read(&mut self.buffer[self.top..]) {
+                Ok(0) => {
+                    self.eof = true;
+                    break;
+                }
+                Ok(n) => self.top += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e)
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> StreamDecoder<R> {
+    /// Seeks to approximately `time` with [`Decoder::seek`]. Returns `false` if the position can't be found yet
+    /// because no frame has been decoded, or the stream is free-format.
+    ///
+    /// The reader must have been at the start of the stream when the `StreamDecoder` was created.
+    pub fn seek(&mut self, time: Duration) -> io::Result<bool> {
+        match self.decoder.seek(time) {
+            Some(offset) => self.jump(offset).map(|()| true),
+            None => Ok(false)
+        }
+    }
+
+    /// Seeks to exactly `sample` with [`Decoder::seek_to_sample`].
+    ///
+    /// The reader must have been at the start of the stream when the `StreamDecoder` was created.
+    pub fn seek_to_sample(&mut self, sample: u64) -> io::Result<()> {
+        let position = self.decoder.position();
+        let offset = self.decoder.seek_to_sample(sample);
+        // When carrying on from the current position, the buffered data is still needed
+        if offset != position {
+            self.jump(offset)?;
+        }
+        Ok(())
+    }
+
+    /// Discards the buffered data and continues reading from `offset`.
+    fn jump(&mut self, offset: u64) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.bottom = 0;
+        self.top = 0;
+        self.eof = false;
+        Ok(())
+    }
+}
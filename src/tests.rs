@@ -4,6 +4,34 @@ use super::*;
 // https://www.marineband.marines.mil/Audio-Resources/The-Complete-Marches-of-John-Philip-Sousa/
 const THE_WASHINGTON_POST_MARCH: &[u8] = include_bytes!("tests/The Washington Post.mp3");
 
+/// The float pipeline, compiled a second time as the reference for the fixed-point one.
+#[cfg(feature = "fixed-point")]
+#[path = "."]
+mod float {
+    #![allow(dead_code)]
+
+    #[path = "float.rs"]
+    mod real;
+    #[path = "minimp3.rs"]
+    pub mod minimp3;
+}
+
+/// The float pipeline without the SIMD kernels, compiled a second time as the reference for them.
+#[cfg(not(feature = "fixed-point"))]
+#[path = "."]
+mod scalar {
+    #![allow(dead_code)]
+
+    #[path = "float.rs"]
+    mod float;
+    mod real {
+        pub use super::float::*;
+        pub const HAVE_SIMD: bool = false;
+    }
+    #[path = "minimp3.rs"]
+    pub mod minimp3;
+}
+
 #[test]
 fn measure_length_of_march() {
     let mut march = THE_WASHINGTON_POST_MARCH;
@@ -21,5 +49,1225 @@ fn measure_length_of_march() {
         }
     }
     
-    assert_eq!(n, 243072);
-}
\ No newline at end of file
+    assert_eq!(n, 241920);
+}
+
+/// Writes big-endian bit fields, for assembling synthetic frames.
+struct BitWriter<'a> {
+    buf: &'a mut [u8],
+    pos: usize
+}
+
+impl<'a> BitWriter<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn put(&mut self, bits: u32, value: u32) {
+        for i in (0..bits).rev() {
+            if value >> i & 1 != 0 {
+                self.buf[self.pos / 8] |= 0x80 >> (self.pos % 8);
+            }
+            self.pos += 1;
+        }
+    }
+}
+
+/// A 192 byte MPEG-1 Layer II frame (64 kbps, 48 kHz, mono) with only subband `band` allocated,
+/// where every sample is `sample` at 4 bits.
+fn layer2_frame(band: u32, sample: u32, protected: bool) -> [u8; 192] {
+    let mut frame = [0; 192];
+    let mut w = BitWriter::new(&mut frame);
+    if protected {
+        w.put(32, 0xfffc44c0);
+        w.put(16, 0);
+    } else {
+        w.put(32, 0xfffd44c0);
+    }
+    // 27 bands for this bitrate: 3+8 4-bit, 12 3-bit and 4 2-bit allocations
+    for i in 0..27 {
+        let bits = match i {
+            0..=10 => 4,
+            11..=22 => 3,
+            _ => 2
+        };
+        // allocation code 3 is 4 bits per sample in the first table
+        w.put(bits, if i == band { 3 } else { 0 });
+    }
+    // scfsi 0: three scalefactors follow
+    w.put(2, 0);
+    for _ in 0..3 {
+        w.put(6, 8);
+    }
+    for _ in 0..12 {
+        for _ in 0..3 {
+            w.put(4, sample);
+        }
+    }
+    if protected {
+        // The allocation and scfsi are protected
+        let crc = minimp3::mp3d_crc16(minimp3::mp3d_crc16(0xffff, &frame[2..4], 16), &frame[6..], 88 + 2);
+        frame[4..6].copy_from_slice(&crc.to_be_bytes());
+    }
+    frame
+}
+
+fn decode_all(mut mp3: &[u8], mut on_frame: impl FnMut(FrameInfo, &[f32])) {
+    let mut decoder = Decoder::new();
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    while !mp3.is_empty() {
+        let (mp3_consumed, frame_info) = decoder.decode(mp3, &mut pcm_buffer);
+        mp3 = &mp3[mp3_consumed..];
+        if let Some(frame_info) = frame_info {
+            on_frame(frame_info, &pcm_buffer[..frame_info.samples_produced * usize::from(frame_info.channels.num())]);
+        }
+    }
+}
+
+#[test]
+fn decode_layer2() {
+    let mut mp2 = [0u8; 192 * 6];
+    for (i, frame) in mp2.chunks_exact_mut(192).enumerate() {
+        frame.copy_from_slice(&layer2_frame(0, if i < 3 { 7 } else { 15 }, false));
+    }
+
+    let mut frames = 0;
+    let mut peak = 0f32;
+    decode_all(&mp2, |info, pcm| {
+        assert_eq!(info.samples_produced, 1152);
+        assert_eq!(info.channels, Channels::Mono);
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.bitrate, 64);
+        // a sample of 7 is the zero point of a 4-bit allocation
+        if frames < 3 {
+            assert!(pcm.iter().all(|&s| s == 0.));
+        } else {
+            peak = pcm.iter().fold(peak, |peak, s| peak.max(s.abs()));
+        }
+        frames += 1;
+    });
+
+    assert_eq!(frames, 6);
+    assert!(peak > 0.01 && peak < 1., "peak {peak}");
+}
+
+/// A 64 byte MPEG-1 Layer I frame (64 kbps, 48 kHz, mono) with only subband `band` allocated,
+/// where every sample is `sample` at 4 bits.
+fn layer1_frame(band: u32, sample: u32) -> [u8; 64] {
+    let mut frame = [0; 64];
+    let mut w = BitWriter::new(&mut frame);
+    w.put(32, 0xffff24c0);
+    for i in 0..32 {
+        // allocation code 3 is 4 bits per sample
+        w.put(4, if i == band { 3 } else { 0 });
+    }
+    w.put(6, 8);
+    for _ in 0..12 {
+        w.put(4, sample);
+    }
+    frame
+}
+
+#[test]
+fn decode_layer1() {
+    let mut mp1 = [0u8; 64 * 6];
+    for (i, frame) in mp1.chunks_exact_mut(64).enumerate() {
+        frame.copy_from_slice(&layer1_frame(0, if i < 3 { 7 } else { 15 }));
+    }
+
+    let mut frames = 0;
+    let mut peak = 0f32;
+    decode_all(&mp1, |info, pcm| {
+        assert_eq!(info.samples_produced, 384);
+        assert_eq!(info.channels, Channels::Mono);
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.bitrate, 64);
+        if frames < 3 {
+            assert!(pcm.iter().all(|&s| s == 0.));
+        } else {
+            peak = pcm.iter().fold(peak, |peak, s| peak.max(s.abs()));
+        }
+        frames += 1;
+    });
+
+    assert_eq!(frames, 6);
+    assert!(peak > 0.01 && peak < 1., "peak {peak}");
+}
+
+
+#[test]
+fn decode_errors() {
+    let mut decoder = Decoder::new();
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+
+    assert_eq!(decoder.try_decode(&[], &mut pcm_buffer), (0, Err(DecodeError::InsufficientData)));
+    assert_eq!(decoder.try_decode(&[0; 100], &mut pcm_buffer), (100, Err(DecodeError::SkippedData)));
+
+    // Frames partway through the march depend on the bit reservoir of previous frames
+    let (consumed, result) = decoder.try_decode(&THE_WASHINGTON_POST_MARCH[25046 + 960 * 2..], &mut pcm_buffer);
+    assert_eq!(consumed, 960);
+    assert_eq!(result.unwrap_err(), DecodeError::ReservoirNotFilled);
+
+    // A Layer III frame whose big_values exceeds the granule size
+    let mut mp3 = [0; 192];
+    let mut w = BitWriter::new(&mut mp3);
+    w.put(32, 0xfffb54c0);
+    w.put(18, 0);
+    w.put(12, 100);
+    w.put(9, 511);
+    assert_eq!(decoder.try_decode(&mp3, &mut pcm_buffer), (192, Err(DecodeError::CorruptFrame)));
+}
+
+#[test]
+fn decode_always_makes_progress() {
+    let mut decoder = Decoder::new();
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    assert_eq!(decoder.decode(&[0; 3], &mut pcm_buffer), (3, None));
+    assert_eq!(decoder.decode(&[0; 16384], &mut pcm_buffer), (16384, None));
+
+    // Junk followed by a stream joined partway through, whose first frames can't be decoded
+    let mut mp3 = [0xffu8; 50000];
+    mp3[1000..].copy_from_slice(&THE_WASHINGTON_POST_MARCH[50000 + 1000..100000]);
+    let mut frames = 0;
+    decode_all(&mp3, |info, _| {
+        assert_eq!(info.sample_rate, 48000);
+        frames += 1;
+    });
+    assert!(frames > 0);
+}
+
+#[test]
+fn decode_damaged_frames() {
+    let mut seed = 1u32;
+    let mut mp3 = [0u8; 50000];
+    for damage in [5, 50, 500, 5000] {
+        mp3.copy_from_slice(&THE_WASHINGTON_POST_MARCH[..50000]);
+        for _ in 0..damage {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            mp3[seed as usize % mp3.len()] = (seed >> 24) as u8;
+        }
+        decode_all(&mp3, |_, _| {});
+    }
+
+    // Intensity stereo, which the march doesn't use, with mid-side stereo for the bands below it
+    mp3.copy_from_slice(&THE_WASHINGTON_POST_MARCH[..50000]);
+    for i in 0..mp3.len() - 4 {
+        if mp3[i] == 0xff && mp3[i + 1] & 0xe6 == 0xe2 {
+            mp3[i + 3] = mp3[i + 3] & 0xf | 0x70;
+        }
+    }
+    let mut frames = 0;
+    decode_all(&mp3, |_, _| frames += 1);
+    assert!(frames > 0);
+}
+
+#[test]
+fn parse_frame_header() {
+    let header = FrameHeader::parse(THE_WASHINGTON_POST_MARCH[25046..25050].try_into().unwrap()).unwrap();
+    assert_eq!(header.version(), Version::Mpeg1);
+    assert_eq!(header.layer(), Layer::III);
+    assert!(!header.is_protected());
+    assert_eq!(header.bitrate(), 320);
+    assert_eq!(header.sample_rate(), 48000);
+    assert!(!header.has_padding());
+    assert_eq!(header.channel_mode(), ChannelMode::JointStereo);
+    assert_eq!(header.mode_extension(), 0);
+    assert!(!header.is_copyrighted());
+    assert!(header.is_original());
+    assert_eq!(header.emphasis(), Emphasis::None);
+    assert_eq!(header.channels(), Channels::Stereo);
+    assert_eq!(header.samples_per_frame(), 1152);
+    assert_eq!(header.frame_bytes(), Some(960));
+
+    let header = FrameHeader::parse([0xff, 0xfd, 0x46, 0xc0]).unwrap();
+    assert_eq!(header.layer(), Layer::II);
+    assert_eq!(header.bitrate(), 64);
+    assert!(header.has_padding());
+    assert_eq!(header.channel_mode(), ChannelMode::Mono);
+    assert_eq!(header.frame_bytes(), Some(193));
+
+    // MPEG-2.5 Layer III, 8 kHz, free format, protected
+    let header = FrameHeader::parse([0xff, 0xe2, 0x08, 0x00]).unwrap();
+    assert_eq!(header.version(), Version::Mpeg2_5);
+    assert!(header.is_protected());
+    assert_eq!(header.sample_rate(), 8000);
+    assert_eq!(header.samples_per_frame(), 576);
+    assert_eq!(header.frame_bytes(), None);
+
+    assert_eq!(FrameHeader::parse(*b"ID3\x04"), None);
+    // reserved sample rate
+    assert_eq!(FrameHeader::parse([0xff, 0xfb, 0x0c, 0x00]), None);
+}
+
+#[test]
+fn skip_frames_of_march() {
+    let mut march = THE_WASHINGTON_POST_MARCH;
+    let mut decoder = Decoder::new();
+    let mut frames = 0;
+    let mut n = 0;
+    while !march.is_empty() {
+        let (mp3_consumed, result) = decoder.skip_frame(march);
+        march = &march[mp3_consumed..];
+        if let Ok(frame_info) = result {
+            assert_eq!(frame_info.frame_bytes, 960);
+            frames += 1;
+            n += frame_info.samples_produced;
+        }
+    }
+
+    assert_eq!(frames, 210);
+    assert_eq!(n, 241920);
+
+    // Decoding can resume after skipping, once the bit reservoir refills
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut march = THE_WASHINGTON_POST_MARCH;
+    for _ in 0..10 {
+        march = &march[decoder.skip_frame(march).0..];
+    }
+    let (mp3_consumed, result) = decoder.try_decode(march, &mut pcm_buffer);
+    assert_eq!((mp3_consumed, result.unwrap_err()), (960, DecodeError::ReservoirNotFilled));
+    march = &march[mp3_consumed..];
+    assert_eq!(decoder.try_decode(march, &mut pcm_buffer).1.unwrap().samples_produced, 1152);
+}
+
+#[test]
+fn verify_crc() {
+    assert_eq!(minimp3::mp3d_crc16(0xffff, b"123456789", 72), 0xaee7);
+
+    let mut mp2 = [0u8; 192 * 4];
+    for frame in mp2.chunks_exact_mut(192) {
+        frame.copy_from_slice(&layer2_frame(0, 15, true));
+    }
+    // Flip a bit in the allocation of the third frame
+    mp2[192 * 2 + 7] ^= 0x10;
+
+    let mut crc_ok = [true; 4];
+    let mut i = 0;
+    decode_all(&mp2, |info, _| {
+        crc_ok[i] = info.crc_ok;
+        i += 1;
+    });
+    assert_eq!(crc_ok, [true, true, false, true]);
+
+    for policy in [CrcPolicy::Drop, CrcPolicy::Conceal] {
+        let mut decoder = Decoder::new();
+        decoder.set_crc_policy(policy);
+        let mut pcm_buffer = [1f32; MAX_SAMPLES_PER_FRAME];
+        let mut mp2 = &mp2[..];
+        for _ in 0..2 {
+            mp2 = &mp2[decoder.decode(mp2, &mut pcm_buffer).0..];
+        }
+        let (consumed, result) = decoder.try_decode(mp2, &mut pcm_buffer);
+        assert_eq!(consumed, 192);
+        match policy {
+            CrcPolicy::Drop => assert_eq!(result, Err(DecodeError::CrcMismatch)),
+            _ => {
+                let info = result.unwrap();
+                assert!(!info.crc_ok);
+                assert_eq!(info.samples_produced, 1152);
+                assert!(pcm_buffer[..1152].iter().all(|&s| s == 0.));
+            }
+        }
+        assert!(decoder.try_decode(&mp2[consumed..], &mut pcm_buffer).1.unwrap().crc_ok);
+    }
+}
+
+#[test]
+fn skip_id3v2_tag() {
+    let mut decoder = Decoder::new();
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    assert_eq!(decoder.try_decode(THE_WASHINGTON_POST_MARCH, &mut pcm_buffer), (25046, Err(DecodeError::SkippedData)));
+
+    // A tag split across buffers is still skipped whole
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.try_decode(&THE_WASHINGTON_POST_MARCH[..1000], &mut pcm_buffer), (1000, Err(DecodeError::SkippedData)));
+    assert_eq!(decoder.try_decode(&THE_WASHINGTON_POST_MARCH[1000..], &mut pcm_buffer), (24046, Err(DecodeError::SkippedData)));
+    assert_eq!(decoder.try_decode(&THE_WASHINGTON_POST_MARCH[25046..], &mut pcm_buffer).0, 960);
+
+    // ID3v2.4 footer
+    assert_eq!(id3::tag_len(b"ID3\x04\x00\x10\x00\x00\x01\x00"), Some(10 + 128 + 10));
+    // sizes are syncsafe
+    assert_eq!(id3::tag_len(b"ID3\x03\x00\x00\x00\x00\x80\x00"), None);
+
+    // Frames embedded in a tag aren't decoded, even in a second tag straight after the first
+    const AUDIO: &[u8] = THE_WASHINGTON_POST_MARCH.split_at(25046).1;
+    let mut mp3 = [0; 10 + 960 * 5 + 10 + 960 * 2 + AUDIO.len()];
+    mp3[..10].copy_from_slice(b"ID3\x03\x00\x00\x00\x00\x25\x40");
+    mp3[10..10 + 960 * 5].copy_from_slice(&AUDIO[..960 * 5]);
+    let (_, mp3_rest) = mp3.split_at_mut(10 + 960 * 5);
+    mp3_rest[..10].copy_from_slice(b"ID3\x03\x00\x00\x00\x00\x0f\x00");
+    mp3_rest[10..10 + 960 * 2].copy_from_slice(&AUDIO[..960 * 2]);
+    mp3_rest[10 + 960 * 2..].copy_from_slice(AUDIO);
+    let mut frames = 0;
+    decode_all(&mp3, |_, _| frames += 1);
+    assert_eq!(frames, 210);
+
+    // Only a tag at the start of the stream or after another tag is skipped, as "ID3" can turn up in audio data
+    assert_eq!(id3::tag_len(b"ID3\x05\x00\x00\x00\x00\x00\x00"), None);
+    let mut mp3 = [0; 960 * 20 + 10 + AUDIO.len()];
+    mp3[..960 * 20].copy_from_slice(&AUDIO[..960 * 20]);
+    mp3[960 * 20 + 10..].copy_from_slice(AUDIO);
+    let mut junk_frames = 0;
+    decode_all(&mp3, |_, _| junk_frames += 1);
+    mp3[960 * 20..960 * 20 + 10].copy_from_slice(b"ID3\x03\x00\x00\x00\x00\x0f\x00");
+    let mut frames = 0;
+    decode_all(&mp3, |_, _| frames += 1);
+    assert_eq!(frames, junk_frames);
+}
+
+#[test]
+fn parse_id3v2_tag() {
+    let tag = id3::Tag::parse(THE_WASHINGTON_POST_MARCH).unwrap();
+    assert_eq!(tag.version(), (4, 0));
+    assert_eq!(tag.len(), 25046);
+    assert_eq!(tag.title().unwrap(), "The Washington Post");
+    assert_eq!(tag.artist().unwrap(), "\"The President's Own\" United States Marine Band");
+    assert_eq!(tag.album().unwrap(), "The Complete Marches of John Philip Sousa: Vol. 3 (1889-1898)");
+    assert_eq!(tag.track().unwrap(), "1");
+    assert_eq!(tag.text("TCON").unwrap().encoding(), id3::Encoding::Utf16);
+    let id3::Content::Comment { language, description, text } = tag.get("COMM").unwrap().content() else { panic!() };
+    assert_eq!(&language, b"eng");
+    assert!(description == "" && text == "");
+    let id3::Content::Picture { mime_type, picture_type, description, data } = tag.get("APIC").unwrap().content() else { panic!() };
+    assert!(mime_type == "image/png" && picture_type == 3 && description == "");
+    assert!(data.starts_with(b"\x89PNG"));
+    assert_eq!(data.len(), 22963 - 1 - 10 - 1 - 1);
+    assert_eq!(tag.frames().count(), 10);
+
+    // ID3v2.2 with three character frame IDs
+    let v22 = b"ID3\x02\x00\x00\x00\x00\x00\x4f\
+        TT2\x00\x00\x07\x00Title\x00\
+        TXX\x00\x00\x0a\x03key\x00caf\xc3\xa9\
+        COM\x00\x00\x0a\x00engabcdef\
+        PIC\x00\x00\x0a\x00JPG\x00\x00\xff\xd8\xff\xe0\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+    let tag = id3::Tag::parse(v22).unwrap();
+    assert_eq!(tag.title().unwrap(), "Title");
+    let id3::Content::UserText { description, value } = tag.get("TXXX").unwrap().content() else { panic!() };
+    assert_eq!(description, "key");
+    assert_eq!(value.as_str(), Some("café"));
+    let id3::Content::Comment { language, description, text } = tag.get("COMM").unwrap().content() else { panic!() };
+    assert_eq!(&language, b"eng");
+    assert!(description == "abcdef" && text == "");
+    let id3::Content::Picture { mime_type, picture_type, data, .. } = tag.get("APIC").unwrap().content() else { panic!() };
+    assert!(mime_type == "JPG" && picture_type == 0 && data == b"\xff\xd8\xff\xe0");
+    assert_eq!(tag.frames().count(), 4);
+
+    // ID3v2.3 with unsynchronisation, UTF-16 little endian and Latin-1
+    let mut v23 = *b"ID3\x03\x00\x80\x00\x00\x00\x23\
+        TPE1\x00\x00\x00\x07\x00\x00\x01\xff\x00\xfe\x41\x00\xe9\x00\
+        TALB\x00\x00\x00\x05\x00\x00\x00ab\xe9\x00\
+        \x00\x00";
+    assert_eq!(id3::Tag::parse(&v23).unwrap_err(), id3::Error::Unsynchronised);
+    let tag = id3::Tag::parse_mut(&mut v23).unwrap();
+    assert_eq!(tag.artist().unwrap(), "Aé");
+    assert_eq!(tag.album().unwrap(), "abé");
+    assert_eq!(tag.album().unwrap().as_str(), None);
+
+    // ID3v2.4 with an unsynchronised frame and multiple values
+    let mut v24 = *b"ID3\x04\x00\x00\x00\x00\x00\x1c\
+        TPE1\x00\x00\x00\x06\x00\x02\x03A\x00B\xff\x00\
+        TRCK\x00\x00\x00\x02\x00\x00\x003";
+    assert_eq!(id3::Tag::parse(&v24).unwrap_err(), id3::Error::Unsynchronised);
+    let tag = id3::Tag::parse_mut(&mut v24).unwrap();
+    let mut artists = tag.artist().unwrap().values();
+    assert_eq!(artists.next().unwrap(), "A");
+    assert_eq!(artists.next().unwrap(), "B\u{fffd}");
+    assert_eq!(artists.next(), None);
+    assert_eq!(tag.track().unwrap(), "3");
+
+    assert_eq!(id3::Tag::parse(&THE_WASHINGTON_POST_MARCH[..1000]).unwrap_err(), id3::Error::Truncated);
+    assert_eq!(id3::Tag::parse(&THE_WASHINGTON_POST_MARCH[25046..]).unwrap_err(), id3::Error::NotATag);
+
+    // Extended headers larger than the tag, or too small to hold their own fields
+    let mut v24 = [0; 30];
+    v24[..14].copy_from_slice(b"ID3\x04\x00\x40\x00\x00\x00\x14\x7f\x7f\x7f\x7f");
+    assert_eq!(id3::Tag::parse(&v24).unwrap_err(), id3::Error::Truncated);
+    assert_eq!(id3::Tag::parse_mut(&mut v24.clone()).unwrap_err(), id3::Error::Truncated);
+    v24[10..14].copy_from_slice(b"\x00\x00\x00\x02");
+    assert_eq!(id3::Tag::parse_mut(&mut v24.clone()).unwrap_err(), id3::Error::Malformed);
+    let mut v23 = [0; 30];
+    v23[..14].copy_from_slice(b"ID3\x03\x00\x40\x00\x00\x00\x14\xff\xff\xff\xff");
+    assert_eq!(id3::Tag::parse(&v23).unwrap_err(), id3::Error::Truncated);
+
+    // A frame size that would overflow the end of the frame
+    let v23 = *b"ID3\x03\x00\x00\x00\x00\x00\x0aTPE1\xff\xff\xff\xff\x00\x00";
+    assert_eq!(id3::Tag::parse(&v23).unwrap().frames().count(), 0);
+}
+
+#[test]
+fn parse_vbr_header() {
+    let march = &THE_WASHINGTON_POST_MARCH[25046..];
+    let mut decoder = Decoder::new();
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    assert_eq!(decoder.try_decode(march, &mut pcm_buffer), (960, Err(DecodeError::VbrHeader)));
+    let vbr_info = *decoder.vbr_info().unwrap();
+    assert_eq!(vbr_info.kind, VbrKind::Info);
+    assert_eq!(vbr_info.header, FrameHeader::parse([0xff, 0xfb, 0xe4, 0x44]).unwrap());
+    assert_eq!(vbr_info.frames, Some(210));
+    assert_eq!(vbr_info.bytes, Some(211 * 960));
+    assert_eq!(vbr_info.toc.unwrap()[..4], [0, 3, 6, 8]);
+    assert_eq!(vbr_info.quality, Some(57));
+    assert_eq!(vbr_info.total_samples(), Some(241920));
+    assert_eq!(vbr_info.duration(), Some(core::time::Duration::from_millis(5040)));
+
+    // The first audio frame's main data begins in the VBR header frame, so it decodes straight away
+    assert_eq!(decoder.try_decode(&march[960..], &mut pcm_buffer).1.unwrap().samples_produced, 1152);
+
+    // Only the first frame found is checked
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.try_decode(&march[960..], &mut pcm_buffer).1, Err(DecodeError::ReservoirNotFilled));
+    assert_eq!(decoder.try_decode(march, &mut pcm_buffer).1.unwrap().samples_produced, 1152);
+    assert!(decoder.vbr_info().is_none());
+
+    // Frames that fail to decode don't count as the start of the stream
+    assert_eq!(decoder.seek(core::time::Duration::ZERO), Some(960));
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.try_decode(&march[960..], &mut pcm_buffer).1, Err(DecodeError::ReservoirNotFilled));
+    assert_eq!(decoder.seek(core::time::Duration::ZERO), None);
+
+    // A frame that looks like a VBR header after a resync partway through is decoded as audio
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.try_decode(march, &mut pcm_buffer).1, Err(DecodeError::VbrHeader));
+    assert_eq!(decoder.try_decode(&march[960..], &mut pcm_buffer).1.unwrap().samples_produced, 1152);
+    assert_eq!(decoder.try_decode(&[0; 100], &mut pcm_buffer).1, Err(DecodeError::SkippedData));
+    assert_eq!(decoder.try_decode(march, &mut pcm_buffer).1.unwrap().samples_produced, 1152);
+    assert_eq!(decoder.vbr_info(), Some(&vbr_info));
+
+    // Skipping reports the header too
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.skip_frame(march), (960, Err(DecodeError::VbrHeader)));
+    assert_eq!(decoder.vbr_info(), Some(&vbr_info));
+}
+
+#[test]
+fn gapless_decoding() {
+    let march = &THE_WASHINGTON_POST_MARCH[25046..];
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.skip_frame(march).1, Err(DecodeError::VbrHeader));
+    let lame = decoder.vbr_info().unwrap().lame.unwrap();
+    assert_eq!(&lame.encoder, b"LAME3.99r");
+    assert_eq!((lame.revision, lame.vbr_method), (0, 1));
+    assert_eq!(lame.lowpass, 20500);
+    assert_eq!(lame.peak, 0.);
+    assert_eq!((lame.track_gain, lame.album_gain), (None, None));
+    assert_eq!(lame.bitrate, 255);
+    assert_eq!((lame.encoder_delay, lame.padding), (576, 961));
+    // The fixture was cut from a longer stream, and its Info header rewritten without updating the CRC
+    assert_eq!(lame.music_length, 6317760);
+    assert!(!lame.crc_ok);
+    let mut info_frame = [0; 960];
+    info_frame.copy_from_slice(&march[..960]);
+    info_frame[190..192].copy_from_slice(&[0x43, 0x2f]);
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.skip_frame(&info_frame).1, Err(DecodeError::VbrHeader));
+    assert!(decoder.vbr_info().unwrap().lame.unwrap().crc_ok);
+
+    // The CRC covers the frame up to the CRC field, which moves with the length of the side info
+    for (header, side_info_bytes, frame_bytes) in [([0xff, 0xfb, 0xe4, 0xc4], 17, 960), ([0xff, 0xf3, 0xe4, 0xc4], 9, 480)] {
+        let mut frame = [0; 960];
+        frame[..4].copy_from_slice(&header);
+        let tag = 4 + side_info_bytes;
+        frame[tag..tag + 154].copy_from_slice(&march[36..36 + 154]);
+        let crc = vbr::crc16(&frame[..tag + 154]);
+        frame[tag + 154..tag + 156].copy_from_slice(&crc.to_be_bytes());
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.skip_frame(&frame[..frame_bytes]).1, Err(DecodeError::VbrHeader));
+        assert_eq!(decoder.vbr_info().unwrap().header.channels(), Channels::Mono);
+        assert!(decoder.vbr_info().unwrap().lame.unwrap().crc_ok);
+    }
+
+    // The first 576 + 529 samples are trimmed, and the last 961 - 529
+    let mut decoder = Decoder::new();
+    decoder.set_gapless(true);
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut ungapped_pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut ungapped_decoder = Decoder::new();
+    let mut mp3 = march;
+    let mut samples = [0; 210];
+    for frame in 0..=210 {
+        let (consumed, result) = decoder.try_decode(mp3, &mut pcm_buffer);
+        assert_eq!(ungapped_decoder.try_decode(mp3, &mut ungapped_pcm_buffer).0, consumed);
+        mp3 = &mp3[consumed..];
+        if frame == 0 {
+            assert_eq!(result, Err(DecodeError::VbrHeader));
+            continue;
+        }
+        samples[frame - 1] = result.unwrap().samples_produced;
+        if frame == 1 {
+            assert_eq!(pcm_buffer[..47 * 2], ungapped_pcm_buffer[1105 * 2..1152 * 2]);
+        }
+    }
+    assert!(mp3.is_empty());
+    assert_eq!((samples[0], samples[1], samples[208], samples[209]), (47, 1152, 1152, 720));
+    assert_eq!(samples.iter().sum::<usize>(), 241920 - 1105 - 432);
+
+    // Skipped frames are trimmed too
+    let mut decoder = Decoder::new();
+    decoder.set_gapless(true);
+    let mut n = 0;
+    let mut mp3 = march;
+    while !mp3.is_empty() {
+        let (consumed, result) = decoder.skip_frame(mp3);
+        mp3 = &mp3[consumed..];
+        n += result.map_or(0, |frame_info| frame_info.samples_produced);
+    }
+    assert_eq!(n, 241920 - 1105 - 432);
+}
+
+#[test]
+fn parse_vbri_header() {
+    let march = &THE_WASHINGTON_POST_MARCH[25046..];
+    let mut mp3 = [0; 211 * 960];
+    mp3.copy_from_slice(march);
+    mp3[36..960].fill(0);
+    let mut vbri = BitWriter::new(&mut mp3[36..]);
+    for (bits, value) in [(32, u32::from_be_bytes(*b"VBRI")), (16, 1), (16, 576), (16, 75), (32, 211 * 960), (32, 210), (16, 3), (16, 2), (16, 2), (16, 70)] {
+        vbri.put(bits, value);
+    }
+    for _ in 0..3 {
+        vbri.put(16, 70 * 960 / 2);
+    }
+
+    let mut decoder = Decoder::new();
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    assert_eq!(decoder.try_decode(&mp3, &mut pcm_buffer), (960, Err(DecodeError::VbrHeader)));
+    let vbr_info = decoder.vbr_info().unwrap();
+    assert_eq!(vbr_info.kind, VbrKind::Vbri);
+    assert_eq!((vbr_info.frames, vbr_info.bytes, vbr_info.quality), (Some(210), Some(211 * 960), Some(75)));
+    assert_eq!((vbr_info.toc, vbr_info.lame), (None, None));
+    assert_eq!(vbr_info.total_samples(), Some(241920));
+    let vbri = vbr_info.vbri.unwrap();
+    assert_eq!((vbri.version, vbri.delay, vbri.toc_scale, vbri.toc_entry_bytes, vbri.frames_per_entry), (1, 576, 2, 2, 70));
+    assert_eq!(vbri.toc(), [67200; 3]);
+    assert_eq!(decoder.seek(core::time::Duration::from_millis(2400)), Some(960 + 67200));
+    let mut frames = 0;
+    decode_all(&mp3, |_, _| frames += 1);
+    assert_eq!(frames, 210);
+
+    // Long tables are merged down to 256 entries or fewer
+    mp3[36 + 18..960].fill(0);
+    let mut vbri = BitWriter::new(&mut mp3[36 + 18..]);
+    for (bits, value) in [(16, 600), (16, 1), (16, 1), (16, 1)] {
+        vbri.put(bits, value);
+    }
+    for i in 0..600 {
+        vbri.put(8, i % 2 + 1);
+    }
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.skip_frame(&mp3).1, Err(DecodeError::VbrHeader));
+    let vbri = decoder.vbr_info().unwrap().vbri.unwrap();
+    assert_eq!(vbri.frames_per_entry, 4);
+    assert_eq!(vbri.toc(), [6; 150]);
+
+    // Entries too large for the seek table make the header malformed
+    mp3[36 + 18..960].fill(0);
+    let mut vbri = BitWriter::new(&mut mp3[36 + 18..]);
+    for (bits, value) in [(16, 2), (16, 0xffff), (16, 4), (16, 1), (32, u32::MAX), (32, u32::MAX)] {
+        vbri.put(bits, value);
+    }
+    let mut decoder = Decoder::new();
+    assert_ne!(decoder.skip_frame(&mp3).1, Err(DecodeError::VbrHeader));
+    assert!(decoder.vbr_info().is_none());
+}
+
+#[test]
+fn seek_by_time() {
+    use core::time::Duration;
+
+    let mut decoder = Decoder::new();
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    assert_eq!(decoder.seek(Duration::ZERO), None);
+    let mut march = THE_WASHINGTON_POST_MARCH;
+    while decoder.try_decode(march, &mut pcm_buffer).1 != Err(DecodeError::VbrHeader) {
+        march = &THE_WASHINGTON_POST_MARCH[decoder.position() as usize..];
+    }
+    assert_eq!(decoder.position(), 25046 + 960);
+
+    // Halfway through, from the Info header's seek table, which lands mid-frame
+    let offset = decoder.seek(Duration::from_millis(2520)).unwrap();
+    assert_eq!((offset, decoder.position()), (25046 + 101280, 25046 + 101280));
+    let mut march = &THE_WASHINGTON_POST_MARCH[offset as usize..];
+    let (consumed, result) = decoder.try_decode(march, &mut pcm_buffer);
+    assert_eq!((consumed, result), (480 + 960, Err(DecodeError::ReservoirNotFilled)));
+    march = &march[consumed..];
+    let mut n = 0;
+    while !march.is_empty() {
+        let (consumed, result) = decoder.try_decode(march, &mut pcm_buffer);
+        march = &march[consumed..];
+        n += result.unwrap().samples_produced;
+    }
+    assert_eq!(n, 104 * 1152);
+
+    // Past the end, the seek table gives the start of the last hundredth of the stream
+    let end = decoder.seek(Duration::from_millis(5040)).unwrap();
+    assert_eq!(decoder.seek(Duration::from_secs(1 << 40)), Some(end));
+    assert_eq!(decoder.seek(Duration::MAX), Some(end));
+
+    // Without a VBR header, the stream is assumed to have a constant bit rate
+    let audio = &THE_WASHINGTON_POST_MARCH[25046 + 960..];
+    let mut decoder = Decoder::new();
+    decoder.try_decode(audio, &mut pcm_buffer).1.unwrap_err();
+    decoder.try_decode(&audio[960..], &mut pcm_buffer).1.unwrap();
+    assert_eq!(decoder.seek(Duration::from_secs(1)), Some(42 * 960));
+    assert_eq!(decoder.try_decode(&audio[42 * 960..], &mut pcm_buffer).0, 960);
+    let far = 960 + (1 << 40) * 48000 / 1152 * 960;
+    assert_eq!(decoder.seek(Duration::from_secs(1 << 40)), Some(far));
+    assert_eq!(decoder.position(), far);
+    assert!(decoder.seek(Duration::MAX).unwrap() > far);
+
+    // With gapless decoding, time is measured from the end of the encoder delay, so the start is the Info frame
+    let mut decoder = Decoder::new();
+    decoder.set_gapless(true);
+    let march = &THE_WASHINGTON_POST_MARCH[25046..];
+    assert_eq!(decoder.try_decode(march, &mut pcm_buffer).1, Err(DecodeError::VbrHeader));
+    assert_eq!(decoder.try_decode(&march[960..], &mut pcm_buffer).1.unwrap().samples_produced, 47);
+    assert_eq!(decoder.seek(Duration::ZERO), Some(0));
+    assert_eq!(decoder.try_decode(march, &mut pcm_buffer).1, Err(DecodeError::VbrHeader));
+    assert_eq!(decoder.try_decode(&march[960..], &mut pcm_buffer).1.unwrap().samples_produced, 47);
+}
+
+#[test]
+fn seek_to_exact_sample() {
+    const TARGET: usize = 86 * 1152 + 928;
+    let march = THE_WASHINGTON_POST_MARCH;
+    let mut decoder = Decoder::new();
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut expected = [0f32; (1152 - 928 + 1152) * 2];
+    let mut mp3 = march;
+    let mut n = 0;
+    while !mp3.is_empty() {
+        let (consumed, result) = decoder.try_decode(mp3, &mut pcm_buffer);
+        mp3 = &mp3[consumed..];
+        if let Ok(frame_info) = result {
+            if n == 86 * 1152 {
+                expected[..(1152 - 928) * 2].copy_from_slice(&pcm_buffer[928 * 2..1152 * 2]);
+            } else if n == 87 * 1152 {
+                expected[(1152 - 928) * 2..].copy_from_slice(&pcm_buffer[..1152 * 2]);
+            }
+            n += frame_info.samples_produced;
+        }
+    }
+
+    // Every frame of the march is 960 bytes, so seeking backwards jumps to two frames before the target:
+    // one to fill the bit reservoir, which is passed over, and one to prime the decoder, which is discarded
+    let offset = decoder.seek_to_sample(TARGET as u64);
+    assert_eq!(offset, 25046 + 960 + 84 * 960);
+    let mut mp3 = &march[offset as usize..];
+    let mut frames = 0;
+    let first_frame = loop {
+        let (consumed, result) = decoder.try_decode(mp3, &mut pcm_buffer);
+        mp3 = &mp3[consumed..];
+        frames += 1;
+        let frame_info = result.unwrap();
+        if frame_info.samples_produced != 0 {
+            break frame_info;
+        }
+    };
+    assert_eq!(frames, 3);
+    assert_eq!(first_frame.samples_produced, 1152 - 928);
+    assert_eq!(pcm_buffer[..(1152 - 928) * 2], expected[..(1152 - 928) * 2]);
+    assert_eq!(decoder.try_decode(mp3, &mut pcm_buffer).1.unwrap().samples_produced, 1152);
+    assert_eq!(pcm_buffer[..1152 * 2], expected[(1152 - 928) * 2..]);
+
+    // Seeking a little forwards carries on from the current position, and further forwards jumps
+    let position = decoder.position();
+    assert_eq!(decoder.seek_to_sample(88 * 1152 + 100), position);
+    assert_eq!(decoder.seek_to_sample(150_000), 25046 + 960 + 128 * 960);
+    let mut mp3 = &march[25046 + 960 + 128 * 960..];
+    let mut n = 0;
+    while !mp3.is_empty() {
+        let (consumed, result) = decoder.try_decode(mp3, &mut pcm_buffer);
+        mp3 = &mp3[consumed..];
+        n += result.unwrap().samples_produced;
+    }
+    assert_eq!(n, 241920 - 150_000);
+
+    // Seeking to the first frames starts from the Info frame, which holds main data for the first audio frame
+    assert_eq!(decoder.seek_to_sample(1000), 25046);
+    decoder.set_gapless(true);
+    assert_eq!(decoder.seek_to_sample(u64::MAX), 25046 + 960 + (u64::MAX / 1152 - 2) * 960);
+
+    // Frames of a variable bit rate stream vary in length, so an exact seek backwards restarts from the first frame
+    let mut vbr = [0; 960 * 4];
+    vbr.copy_from_slice(&march[25046..25046 + 960 * 4]);
+    vbr[36..40].copy_from_slice(b"Xing");
+    let mut decoder = Decoder::new();
+    let mut mp3 = &vbr[..];
+    while !mp3.is_empty() {
+        mp3 = &mp3[decoder.try_decode(mp3, &mut pcm_buffer).0..];
+    }
+    assert_eq!(decoder.seek_to_sample(2000), 0);
+
+    // After a coarse seek the position is unknown, so an exact seek doesn't carry on even when going forwards
+    decoder.seek(core::time::Duration::from_millis(10)).unwrap();
+    assert_eq!(decoder.seek_to_sample(2000), 0);
+}
+
+#[test]
+fn build_frame_index() {
+    let march = THE_WASHINGTON_POST_MARCH;
+    let mut decoder = Decoder::new();
+    let mut index = FrameIndex::new([0; 8 + 8 * 256]);
+    let mut mp3 = march;
+    while !mp3.is_empty() {
+        let (consumed, _) = index.skip_frame(&mut decoder, mp3).unwrap();
+        mp3 = &mp3[consumed..];
+    }
+    assert_eq!(index.len(), 210);
+    assert_eq!(index.remaining_capacity(), 46);
+    assert_eq!(index.header(), FrameHeader::parse([0xff, 0xfb, 0xe4, 0x44]));
+    assert_eq!(index.get(0), Some(IndexedFrame { offset: 25046 + 960, sample: 0, main_data_begin: 506 }));
+    assert_eq!(index.get(2), Some(IndexedFrame { offset: 25046 + 960 * 3, sample: 2 * 1152, main_data_begin: 491 }));
+    assert_eq!(index.get(210), None);
+    assert_eq!(index.frame_at_sample(100_000), Some(86));
+    assert_eq!(index.frame_at_sample(1_000_000), Some(209));
+
+    // The index is stored serialised
+    let bytes = index.as_bytes();
+    assert_eq!(bytes.len(), 8 + 8 * 210);
+    let loaded = FrameIndex::from_bytes(bytes).unwrap();
+    assert_eq!(loaded.get(209), index.get(209));
+    assert!(FrameIndex::from_bytes(&bytes[..100]).is_none());
+    // A length whose size wraps around to a few bytes on 32-bit targets
+    let mut wrapping = [0; 8 + 8 * 210];
+    wrapping.copy_from_slice(bytes);
+    wrapping[4..8].copy_from_slice(&0xe000_0000u32.to_le_bytes());
+    assert!(FrameIndex::from_bytes(&wrapping[..]).is_none());
+    // Offsets that go backwards
+    let mut unordered = [0; 8 + 8 * 210];
+    unordered.copy_from_slice(bytes);
+    unordered.copy_within(8 + 8 * 2..8 + 8 * 3, 8);
+    assert!(FrameIndex::from_bytes(&unordered[..]).is_none());
+
+    let mut small_index = FrameIndex::new([0; 8 + 8 * 2]);
+    let mut decoder = Decoder::new();
+    let mut mp3 = &march[25046..];
+    for _ in 0..3 {
+        mp3 = &mp3[small_index.skip_frame(&mut decoder, mp3).unwrap().0..];
+    }
+    assert_eq!(small_index.skip_frame(&mut decoder, mp3), Err(IndexFull));
+
+    // Seeking with the index gives the same output as seeking from the start
+    const TARGET: u64 = 86 * 1152 + 928;
+    let mut pcm_buffer = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut expected = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut decoder = Decoder::new();
+    let mut mp3 = &march[decoder.seek_to_sample(TARGET) as usize..];
+    loop {
+        let (consumed, result) = decoder.try_decode(mp3, &mut expected);
+        mp3 = &mp3[consumed..];
+        if result.is_ok_and(|frame_info| frame_info.samples_produced != 0) {
+            break;
+        }
+    }
+
+    let mut decoder = Decoder::new();
+    let offset = decoder.seek_with_index(&loaded, TARGET).unwrap();
+    // The pre-roll frame 85 refers back into frame 84 for its main data
+    assert_eq!(offset, loaded.get(84).unwrap().offset);
+    let mut mp3 = &march[offset as usize..];
+    let mut frames = 0;
+    let frame_info = loop {
+        let (consumed, result) = decoder.try_decode(mp3, &mut pcm_buffer);
+        mp3 = &mp3[consumed..];
+        frames += 1;
+        if let Some(frame_info) = result.ok().filter(|frame_info| frame_info.samples_produced != 0) {
+            break frame_info;
+        }
+    };
+    assert_eq!(frames, 3);
+    assert_eq!(frame_info.samples_produced, 1152 - 928);
+    assert_eq!(pcm_buffer[..(1152 - 928) * 2], expected[..(1152 - 928) * 2]);
+}
+
+#[test]
+fn decode_sample_formats() {
+    use sample::sealed::Sealed;
+
+    // Rounded and saturated as upstream minimp3 does
+    assert_eq!(i16::scale_pcm(0.4), 0);
+    assert_eq!(i16::scale_pcm(0.5), 1);
+    assert_eq!(i16::scale_pcm(-1.5), -2);
+    assert_eq!(i16::scale_pcm(32766.4), 32766);
+    assert_eq!(i16::scale_pcm(32766.5), 32767);
+    assert_eq!(i16::scale_pcm(40000.), 32767);
+    assert_eq!(i16::scale_pcm(-32767.5), -32768);
+    assert_eq!(i16::scale_pcm(-40000.), -32768);
+    assert_eq!(i32::scale_pcm(-1.), -65536);
+    assert_eq!(i32::scale_pcm(40000.), i32::MAX);
+    assert_eq!(I24::scale_pcm(-1.).to_i32(), -256);
+    assert_eq!(I24::scale_pcm(-1.).to_le_bytes(), [0x00, 0xff, 0xff]);
+    assert_eq!(I24::scale_pcm(40000.), I24::MAX);
+    assert_eq!(I24::scale_pcm(-40000.), I24::MIN);
+
+    let march = THE_WASHINGTON_POST_MARCH;
+    let mut decoders: [Decoder; 5] = core::array::from_fn(|_| Decoder::new());
+    let mut f32_pcm = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut f64_pcm = [0f64; MAX_SAMPLES_PER_FRAME];
+    let mut i16_pcm = [0i16; MAX_SAMPLES_PER_FRAME];
+    let mut i32_pcm = [0i32; MAX_SAMPLES_PER_FRAME];
+    let mut i24_pcm = [I24::default(); MAX_SAMPLES_PER_FRAME];
+    let mut mp3 = march;
+    let mut n = 0;
+    while !mp3.is_empty() {
+        let (consumed, frame_info) = decoders[0].decode(mp3, &mut f32_pcm);
+        assert_eq!(decoders[1].decode(mp3, &mut f64_pcm), (consumed, frame_info));
+        assert_eq!(decoders[2].decode(mp3, &mut i16_pcm), (consumed, frame_info));
+        assert_eq!(decoders[3].decode(mp3, &mut i32_pcm), (consumed, frame_info));
+        assert_eq!(decoders[4].decode(mp3, &mut i24_pcm), (consumed, frame_info));
+        mp3 = &mp3[consumed..];
+        let Some(frame_info) = frame_info else {
+            continue;
+        };
+        let len = frame_info.samples_produced * 2;
+        for i in 0..len {
+            #[cfg(not(feature = "fixed-point"))]
+            {
+                let f = f32_pcm[i];
+                assert_eq!(f64_pcm[i], f64::from(f));
+                // Truncating before the adjustment for negative samples rounds values just below -0.5 to 0
+                assert!((f * 32768. - f32::from(i16_pcm[i])).abs() < 1.5, "{f} {}", i16_pcm[i]);
+                assert!((f64::from(f) * 2147483648. - f64::from(i32_pcm[i])).abs() <= 0.5, "{f} {}", i32_pcm[i]);
+                assert!((f64::from(f) * 8388608. - f64::from(i24_pcm[i].to_i32())).abs() <= 0.5, "{f} {:?}", i24_pcm[i]);
+            }
+            // The fixed-point accumulator converts exactly to `f64`, which the other formats are rounded from
+            #[cfg(feature = "fixed-point")]
+            {
+                let f = f64_pcm[i];
+                assert_eq!(f32_pcm[i], f as f32);
+                assert!((f * 32768. - f64::from(i16_pcm[i])).abs() <= 0.5, "{f} {}", i16_pcm[i]);
+                assert!((f * 2147483648. - f64::from(i32_pcm[i])).abs() <= 0.5, "{f} {}", i32_pcm[i]);
+                assert!((f * 8388608. - f64::from(i24_pcm[i].to_i32())).abs() <= 0.5, "{f} {:?}", i24_pcm[i]);
+            }
+        }
+        n += frame_info.samples_produced;
+    }
+    assert_eq!(n, 241920);
+}
+
+#[test]
+fn decode_planar() {
+    let march = THE_WASHINGTON_POST_MARCH;
+    let mut interleaved_decoder = Decoder::new();
+    let mut planar_decoder = Decoder::new();
+    // Trimming the output moves the samples of each channel separately
+    interleaved_decoder.set_gapless(true);
+    planar_decoder.set_gapless(true);
+    let mut pcm = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut left = [0f32; MAX_SAMPLES_PER_FRAME / 2];
+    let mut right = [0f32; MAX_SAMPLES_PER_FRAME / 2];
+    let mut mp3 = march;
+    let mut n = 0;
+    while !mp3.is_empty() {
+        let (consumed, frame_info) = interleaved_decoder.decode(mp3, &mut pcm);
+        assert_eq!(planar_decoder.decode_planar(mp3, &mut left, &mut right), (consumed, frame_info));
+        mp3 = &mp3[consumed..];
+        let Some(frame_info) = frame_info else {
+            continue;
+        };
+        for (i, frame) in pcm[..frame_info.samples_produced * 2].chunks_exact(2).enumerate() {
+            assert_eq!(frame, [left[i], right[i]]);
+        }
+        n += frame_info.samples_produced;
+    }
+    assert_eq!(n, 240383);
+
+    // Mono streams only use the left channel
+    let mp2 = layer2_frame(0, 15, false);
+    let mut pcm = [0i16; MAX_SAMPLES_PER_FRAME];
+    let mut left = [0i16; MAX_SAMPLES_PER_FRAME / 2];
+    let mut right = [1i16; MAX_SAMPLES_PER_FRAME / 2];
+    let frame_info = Decoder::new().decode(&mp2, &mut pcm).1.unwrap();
+    assert_eq!(Decoder::new().decode_planar(&mp2, &mut left, &mut right).1, Some(frame_info));
+    assert_eq!(pcm[..1152], left);
+    assert!(right.iter().all(|&s| s == 1));
+}
+
+#[test]
+fn decode_with_scratch() {
+    // One scratch shared by decoders taking turns, checked against a decoder working in its own
+    let mut scratch = DecoderScratch::new();
+    let mut decoder = Decoder::new();
+    let mut interleaved_decoder = Decoder::new();
+    let mut planar_decoder = Decoder::new();
+    let mut expected = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut pcm = [0f32; MAX_SAMPLES_PER_FRAME];
+    let mut left = [0f32; MAX_SAMPLES_PER_FRAME / 2];
+    let mut right = [0f32; MAX_SAMPLES_PER_FRAME / 2];
+    let mut mp3 = THE_WASHINGTON_POST_MARCH;
+    let mut n = 0;
+    while !mp3.is_empty() {
+        let (consumed, result) = decoder.try_decode(mp3, &mut expected);
+        assert_eq!(interleaved_decoder.try_decode_with_scratch(mp3, &mut pcm, &mut scratch), (consumed, result));
+        assert_eq!(planar_decoder.try_decode_planar_with_scratch(mp3, &mut left, &mut right, &mut scratch), (consumed, result));
+        mp3 = &mp3[consumed..];
+        let Ok(frame_info) = result else {
+            continue;
+        };
+        let len = frame_info.samples_produced * 2;
+        assert_eq!(pcm[..len], expected[..len]);
+        for (i, frame) in expected[..len].chunks_exact(2).enumerate() {
+            assert_eq!(frame, [left[i], right[i]]);
+        }
+        n += len;
+    }
+    assert_eq!(n, 241920 * 2);
+}
+
+#[test]
+fn iterate_frames() {
+    let march = THE_WASHINGTON_POST_MARCH;
+    let mut frames = Frames::new(march);
+    let mut count = 0;
+    let mut n = 0;
+    decode_all(march, |_, expected| {
+        let (frame_info, pcm) = frames.next_frame().unwrap();
+        assert_eq!(pcm.len(), frame_info.samples_produced * 2);
+        assert_eq!(pcm, expected);
+        count += 1;
+        n += pcm.len();
+    });
+    assert_eq!(count, 210);
+    assert_eq!(n, 241920 * 2);
+    assert_eq!(frames.offset(), march.len());
+    assert!(frames.remaining().is_empty());
+    assert!(frames.next_frame().is_none());
+
+    // A lone frame that is exactly the length of the data has no following header to check against
+    let frame = layer2_frame(0, 15, false);
+    let mut frames = Frames::new(&frame);
+    let (frame_info, _) = frames.next_frame().unwrap();
+    assert_eq!(frame_info.samples_produced, 1152);
+    assert_eq!(frames.offset(), 192);
+    assert!(frames.next_frame().is_none());
+
+    // The same goes for the last of several frames
+    let mut mp2 = [0u8; 192 * 3];
+    for frame in mp2.chunks_exact_mut(192) {
+        frame.copy_from_slice(&layer2_frame(0, 15, false));
+    }
+    let mut frames = Frames::new(&mp2);
+    let mut count = 0;
+    while frames.next_frame().is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 3);
+    assert_eq!(frames.offset(), mp2.len());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn stream_decoder() {
+    use std::{io::Cursor, vec::Vec};
+
+    let march = THE_WASHINGTON_POST_MARCH;
+    let mut expected = Vec::new();
+    decode_all(march, |_, pcm| expected.extend_from_slice(pcm));
+
+    let mut stream = StreamDecoder::new(Cursor::new(march));
+    let mut frames = 0;
+    let mut samples = Vec::new();
+    while let Some((frame_info, pcm)) = stream.next_frame().unwrap() {
+        assert_eq!(pcm.len(), frame_info.samples_produced * 2);
+        frames += 1;
+        samples.extend_from_slice(pcm);
+    }
+    assert_eq!(frames, 210);
+    assert_eq!(samples, expected);
+    assert!(stream.next_frame().unwrap().is_none());
+
+    const TARGET: usize = 86 * 1152 + 928;
+    stream.seek_to_sample(TARGET as u64).unwrap();
+    let (frame_info, pcm) = stream.next_frame().unwrap().unwrap();
+    assert_eq!(frame_info.samples_produced, 1152 - 928);
+    assert_eq!(pcm, &expected[TARGET * 2..87 * 1152 * 2]);
+
+    assert!(stream.seek(core::time::Duration::from_secs(1)).unwrap());
+    let mut n = 0;
+    while let Some((frame_info, _)) = stream.next_frame().unwrap() {
+        n += frame_info.samples_produced;
+    }
+    assert!(n > 0 && n < 241920);
+}
+
+
+/// Decodes `mp3` with the fixed-point pipeline and with the float one side by side. Returns the largest error
+/// of the fixed-point `i16` output from the clipped float output, in LSBs, the energy of the float output and of the
+/// difference of the two, and the number of samples where the float output clips and the fixed-point one has the
+/// opposite sign.
+#[cfg(feature = "fixed-point")]
+fn compare_with_float(mut mp3: &[u8]) -> (f64, f64, f64, usize) {
+    use pcm::Interleaved;
+
+    let mut scratch = minimp3::mp3dec_frame_scratch_t::new();
+    let mut fixed = [minimp3::mp3dec_t::new(); 2];
+    let mut fixed_i16 = [0i16; MAX_SAMPLES_PER_FRAME];
+    let mut fixed_f64 = [0f64; MAX_SAMPLES_PER_FRAME];
+    let mut float_scratch = float::minimp3::mp3dec_frame_scratch_t::new();
+    let mut float = float::minimp3::mp3dec_t::new();
+    let mut float_f64 = [0f64; MAX_SAMPLES_PER_FRAME];
+
+    let (mut max_error, mut signal, mut noise, mut flips) = (0f64, 0f64, 0f64, 0);
+    while !mp3.is_empty() {
+        let mut info = minimp3::mp3dec_frame_info_t::default();
+        let mut float_info = float::minimp3::mp3dec_frame_info_t::default();
+        let (samples, float_samples) = {
+            let samples = minimp3::mp3dec_decode_frame(
+                &mut fixed[0], mp3, Some(&mut Interleaved(&mut fixed_i16)), &mut scratch, &mut info, false, false, true
+            );
+            minimp3::mp3dec_decode_frame(
+                &mut fixed[1], mp3, Some(&mut Interleaved(&mut fixed_f64)), &mut scratch, &mut info, false, false, true
+            );
+            let float_samples = float::minimp3::mp3dec_decode_frame(
+                &mut float, mp3, Some(&mut Interleaved(&mut float_f64)), &mut float_scratch, &mut float_info, false, false, true
+            );
+            (samples, float_samples)
+        };
+        assert_eq!((samples, info.frame_bytes), (float_samples, float_info.frame_bytes));
+        mp3 = &mp3[info.frame_bytes..];
+        for i in 0..samples.max(0) as usize * info.channels as usize {
+            let reference = float_f64[i];
+            let clipped = (reference * 32768.).clamp(-32768., 32767.);
+            max_error = max_error.max((f64::from(fixed_i16[i]) - clipped).abs());
+            if clipped.abs() >= 32767. && f64::from(fixed_i16[i]) * clipped < 0. {
+                flips += 1;
+            }
+            signal += reference * reference;
+            noise += (fixed_f64[i] - reference) * (fixed_f64[i] - reference);
+        }
+    }
+    (max_error, signal, noise, flips)
+}
+
+/// The march with the global gain of every granule raised by `steps` of 1.5 dB, for a master too hot for 16 bits.
+#[cfg(feature = "fixed-point")]
+fn louder_march(steps: u32) -> [u8; THE_WASHINGTON_POST_MARCH.len()] {
+    let mut mp3 = [0; THE_WASHINGTON_POST_MARCH.len()];
+    mp3.copy_from_slice(THE_WASHINGTON_POST_MARCH);
+    for frame in mp3[25046 + 960..].chunks_exact_mut(960) {
+        // The side info for each granule and channel is 59 bits, from 20 bits in, with the global gain 21 bits into it
+        for granule in 0..4 {
+            let start = 32 + 20 + granule * 59 + 21;
+            let gain = (start..start + 8).fold(0, |gain, i| gain << 1 | u32::from(frame[i / 8] >> (7 - i % 8) & 1));
+            let gain = (gain + steps).min(255);
+            for (j, i) in (start..start + 8).enumerate() {
+                frame[i / 8] &= !(0x80 >> (i % 8));
+                frame[i / 8] |= ((gain >> (7 - j) & 1) as u8) << (7 - i % 8);
+            }
+        }
+    }
+    mp3
+}
+
+/// Layer II and Layer I streams with a different subband and sample in each frame, for comparing two decoders.
+fn layer2_and_layer1_streams() -> ([u8; 192 * 8], [u8; 64 * 8]) {
+    let mut mp2 = [0u8; 192 * 8];
+    for (i, frame) in mp2.chunks_exact_mut(192).enumerate() {
+        frame.copy_from_slice(&layer2_frame(i as u32 % 4, [0, 15, 3, 12][i % 4], false));
+    }
+    let mut mp1 = [0u8; 64 * 8];
+    for (i, frame) in mp1.chunks_exact_mut(64).enumerate() {
+        frame.copy_from_slice(&layer1_frame(i as u32 * 3, [0, 15, 3, 12][i % 4]));
+    }
+    (mp2, mp1)
+}
+
+#[test]
+#[cfg(feature = "fixed-point")]
+fn fixed_point_accuracy() {
+    let (mp2, mp1) = layer2_and_layer1_streams();
+    // Peaking about 30 dB over full scale, which still fits in the decoder
+    let loud = louder_march(20);
+    for mp3 in [THE_WASHINGTON_POST_MARCH, &mp2, &mp1, &loud] {
+        let (max_error, signal, noise, _) = compare_with_float(mp3);
+        // Within rounding of the float output, with the error over 100 dB below the signal
+        assert!(max_error < 0.6, "{max_error}");
+        assert!(noise < signal * 1e-10, "{signal} {noise}");
+    }
+
+    // Louder still, values saturate inside the decoder, but still clip at the output rather than wrapping round
+    let (_, _, _, flips) = compare_with_float(&louder_march(22));
+    assert_eq!(flips, 0);
+}
+
+#[test]
+#[cfg(not(feature = "fixed-point"))]
+fn simd_matches_scalar() {
+    use pcm::Interleaved;
+
+    let (mp2, mp1) = layer2_and_layer1_streams();
+    for mut mp3 in [THE_WASHINGTON_POST_MARCH, &mp2, &mp1] {
+        let mut scratch = minimp3::mp3dec_frame_scratch_t::new();
+        let mut simd = minimp3::mp3dec_t::new();
+        let mut simd_pcm = [0f32; MAX_SAMPLES_PER_FRAME];
+        let mut scalar_scratch = scalar::minimp3::mp3dec_frame_scratch_t::new();
+        let mut scalar = scalar::minimp3::mp3dec_t::new();
+        let mut scalar_pcm = [0f32; MAX_SAMPLES_PER_FRAME];
+        while !mp3.is_empty() {
+            let mut info = minimp3::mp3dec_frame_info_t::default();
+            let mut scalar_info = scalar::minimp3::mp3dec_frame_info_t::default();
+            let (samples, scalar_samples) = (
+                minimp3::mp3dec_decode_frame(
+                    &mut simd, mp3, Some(&mut Interleaved(&mut simd_pcm)), &mut scratch, &mut info, false, false, true
+                ),
+                scalar::minimp3::mp3dec_decode_frame(
+                    &mut scalar, mp3, Some(&mut Interleaved(&mut scalar_pcm)), &mut scalar_scratch, &mut scalar_info,
+                    false, false, true
+                ),
+            );
+            assert_eq!((samples, info.frame_bytes), (scalar_samples, scalar_info.frame_bytes));
+            mp3 = &mp3[info.frame_bytes..];
+            let n = samples.max(0) as usize * info.channels as usize;
+            assert!(simd_pcm[..n].iter().zip(&scalar_pcm[..n]).all(|(a, b)| a.to_bits() == b.to_bits()));
+        }
+    }
+}
+
+// AVX2 is only detected at run time with `std`, so CI runs this with `--features std` on a host that has it
+#[test]
+#[cfg(all(target_arch = "x86_64", feature = "std", not(feature = "fixed-point"), not(feature = "forbid-unsafe")))]
+fn avx2_kernels_match_sse2() {
+    use minimp3::simd::{self, x86};
+
+    if !x86::have_avx2() {
+        return;
+    }
+
+    let mut seed = 1u32;
+    let mut random = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32 - 0.5
+    };
+    let mut buffers = [[0f32; 1152]; 2];
+    buffers[0].fill_with(&mut random);
+    buffers[1] = buffers[0];
+    let [sse2, avx2] = &mut buffers;
+
+    unsafe {
+        for n in [12, 18] {
+            assert_eq!(simd::DCT_II_f4(sse2.as_mut_ptr(), n), x86::DCT_II_avx2(avx2.as_mut_ptr(), n));
+            assert_eq!(sse2, avx2);
+        }
+
+        let window = minimp3::tables::L3_IMDCT_GR_G_MDCT_WINDOW[0].as_ptr();
+        let (co, si): ([f32; 9], [f32; 9]) = (core::array::from_fn(|_| random()), core::array::from_fn(|_| random()));
+        simd::imdct36_window_f4(sse2.as_mut_ptr(), sse2[576..].as_mut_ptr(), co.as_ptr(), si.as_ptr(), window);
+        x86::imdct36_window_avx2(avx2.as_mut_ptr(), avx2[576..].as_mut_ptr(), co.as_ptr(), si.as_ptr(), window);
+        assert_eq!(sse2, avx2);
+
+        let mut lins = [[0f32; 17 * 64]; 2];
+        lins[0].fill_with(&mut random);
+        lins[1] = lins[0];
+        let mut acc = [[0f32; 8 * 15]; 2];
+        let xl = sse2.as_ptr();
+        simd::synth_f4(xl, xl.add(576), lins[0].as_mut_ptr().add(15 * 64), acc[0].as_mut_ptr());
+        x86::synth_avx2(xl, xl.add(576), lins[1].as_mut_ptr().add(15 * 64), acc[1].as_mut_ptr());
+        assert_eq!(lins[0], lins[1]);
+        assert_eq!(acc[0], acc[1]);
+    }
+}
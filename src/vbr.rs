@@ -0,0 +1,283 @@
+use core::time::Duration;
+
+use crate::{FrameHeader, Layer};
+
+/// The kind of header found in the first frame of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VbrKind {
+    /// A `Xing` header, written for variable bit rate streams.
+    Xing,
+    /// An `Info` header, which has the same layout as `Xing` but is written for constant bit rate streams.
+    Info,
+    /// A `VBRI` header, written by Fraunhofer encoders. Its details are in [`VbrInfo::vbri`].
+    Vbri
+}
+
+/// The contents of a Xing, Info or VBRI header, which takes the place of the audio in the first frame of a stream
+/// and describes the stream as a whole. Every field is optional in a Xing or Info header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VbrInfo {
+    pub kind: VbrKind,
+    /// The header of the frame holding the VBR header, which matches the format of the audio frames.
+    pub header: FrameHeader,
+    /// The number of audio frames in the stream, excluding the frame holding the VBR header.
+    pub frames: Option<u32>,
+    /// The length of the stream in bytes, including the frame holding the VBR header.
+    pub bytes: Option<u32>,
+    /// The Xing seek table. Entry `i` is the position of `i`% of the way through the stream's duration,
+    /// as a fraction of [`VbrInfo::bytes`] in 256ths.
+    pub toc: Option<[u8; 100]>,
+    /// The encoder's quality indicator, from 0 (best) to 100 (worst).
+    pub quality: Option<u32>,
+    /// The LAME extension following the Xing or Info header, which is also written by other encoders such as FFmpeg.
+    pub lame: Option<LameTag>,
+    /// The fields specific to a VBRI header, including its seek table.
+    pub vbri: Option<Vbri>
+}
+
+impl VbrInfo {
+    /// Parses the VBR header in `frame`, which must start with the frame header.
+    pub(crate) fn parse(frame: &[u8]) -> Option<Self> {
+        let header = FrameHeader::parse(frame.get(..4)?.try_into().unwrap())?;
+        if header.layer() != Layer::III {
+            return None;
+        }
+        // The VBR header replaces the main data, after the side info
+        let offset = 4 + if header.is_protected() { 2 } else { 0 } + header.side_info_bytes();
+        let tag = frame.get(offset..)?;
+        let kind = match tag.get(..4)? {
+            b"Xing" => VbrKind::Xing,
+            b"Info" => VbrKind::Info,
+            _ => return Self::parse_vbri(header, frame)
+        };
+        let flags = u32::from_be_bytes(tag.get(4..8)?.try_into().unwrap());
+        let mut fields = &tag[8..];
+        let mut field = |flag: u32, len: usize| -> Option<Option<&[u8]>> {
+            if flags & flag == 0 {
+                return Some(None);
+            }
+            let (field, rest) = fields.split_at_checked(len)?;
+            fields = rest;
+            Some(Some(field))
+        };
+        let be_u32 = |field: &[u8]| u32::from_be_bytes(field.try_into().unwrap());
+        let frames = field(0x1, 4)?.map(be_u32);
+        let bytes = field(0x2, 4)?.map(be_u32);
+        let toc = field(0x4, 100)?.map(|toc| toc.try_into().unwrap());
+        let quality = field(0x8, 4)?.map(be_u32);
+        Some(Self {
+            kind,
+            header,
+            frames,
+            bytes,
+            toc,
+            quality,
+            lame: LameTag::parse(frame, frame.len() - fields.len()),
+            vbri: None
+        })
+    }
+
+    /// Parses a VBRI header, which is always 32 bytes after the frame header.
+    fn parse_vbri(header: FrameHeader, frame: &[u8]) -> Option<Self> {
+        let tag = frame.get(4 + 32..)?;
+        if tag.get(..4)? != b"VBRI" {
+            return None;
+        }
+        let fields = tag.get(4..26)?;
+        let be_u16 = |i: usize| u16::from_be_bytes([fields[i], fields[i + 1]]);
+        let be_u32 = |i: usize| u32::from_be_bytes(fields[i..i + 4].try_into().unwrap());
+        let toc_scale = be_u16(16);
+        let toc_entry_bytes = be_u16(18);
+        if !(1..=4).contains(&toc_entry_bytes) {
+            return None;
+        }
+        let toc_entries = tag.get(26..26 + usize::from(be_u16(14)) * usize::from(toc_entry_bytes))?;
+
+        // Halve the resolution of long tables until they fit
+        let mut frames_per_entry = u32::from(be_u16(20));
+        let mut merge = 1;
+        while toc_entries.len() / usize::from(toc_entry_bytes) > MAX_VBRI_TOC_LEN * merge {
+            merge *= 2;
+            frames_per_entry *= 2;
+        }
+        let mut toc = [0; MAX_VBRI_TOC_LEN];
+        let mut toc_len = 0;
+        for (i, entry) in toc_entries.chunks_exact(toc_entry_bytes.into()).enumerate() {
+            // A table whose entries don't fit is malformed
+            let bytes = entry.iter().fold(0, |n, &b| n << 8 | u64::from(b)).checked_mul(u64::from(toc_scale))?;
+            toc[i / merge] = u64::from(toc[i / merge]).checked_add(bytes).and_then(|n| u32::try_from(n).ok())?;
+            toc_len = i / merge + 1;
+        }
+
+        Some(Self {
+            kind: VbrKind::Vbri,
+            header,
+            frames: Some(be_u32(10)),
+            bytes: Some(be_u32(6)),
+            toc: None,
+            quality: Some(be_u16(4).into()),
+            lame: None,
+            vbri: Some(Vbri {
+                version: be_u16(0),
+                delay: be_u16(2),
+                toc_scale,
+                toc_entry_bytes,
+                frames_per_entry,
+                toc_len,
+                toc
+            })
+        })
+    }
+
+    /// Returns the offset from the start of the VBR header frame to seek to for `sample`, and the sample at that offset,
+    /// if the header has a seek table.
+    pub(crate) fn seek_point(&self, sample: u64) -> Option<(u64, u64)> {
+        let total_samples = self.total_samples()?;
+        if let Some(vbri) = &self.vbri {
+            let samples_per_entry = u64::from(vbri.frames_per_entry) * self.header.samples_per_frame() as u64;
+            let entries = (sample / samples_per_entry.max(1)).min(vbri.toc().len() as u64) as usize;
+            let offset = self.header.frame_bytes()? as u64 + vbri.toc()[..entries].iter().map(|&bytes| u64::from(bytes)).sum::<u64>();
+            return Some((offset, entries as u64 * samples_per_entry));
+        }
+        let (toc, bytes) = (self.toc?, self.bytes?);
+        let percent = (sample * 100 / total_samples.max(1)).min(99) as usize;
+        Some((u64::from(toc[percent]) * u64::from(bytes) / 256, percent as u64 * total_samples / 100))
+    }
+
+    /// Returns the number of samples per channel in the stream, if the number of frames is known.
+    pub fn total_samples(&self) -> Option<u64> {
+        Some(u64::from(self.frames?) * self.header.samples_per_frame() as u64)
+    }
+
+    /// Returns the duration of the stream, if the number of frames is known.
+    pub fn duration(&self) -> Option<Duration> {
+        let samples = self.total_samples()?;
+        let rate = u64::from(self.header.sample_rate());
+        Some(Duration::from_secs(samples / rate) + Duration::from_nanos(samples % rate * 1_000_000_000 / rate))
+    }
+}
+
+/// The maximum number of entries kept from a VBRI seek table.
+const MAX_VBRI_TOC_LEN: usize = 256;
+
+/// The fields of a VBRI header that aren't shared with Xing headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vbri {
+    pub version: u16,
+    /// The encoder delay in samples.
+    pub delay: u16,
+    /// The scale factor the seek table entries were divided by to fit them in [`Vbri::toc_entry_bytes`].
+    pub toc_scale: u16,
+    /// The size of each stored seek table entry, from 1 to 4 bytes.
+    pub toc_entry_bytes: u16,
+    /// The number of frames covered by each entry of [`Vbri::toc`].
+    pub frames_per_entry: u32,
+    toc_len: usize,
+    toc: [u32; MAX_VBRI_TOC_LEN]
+}
+
+impl Vbri {
+    /// Returns the seek table. Entry `i` is the length in bytes of the `frames_per_entry` frames starting
+    /// at frame `i * frames_per_entry`, already multiplied by [`Vbri::toc_scale`].
+    ///
+    /// Tables longer than 256 entries have neighbouring entries merged, doubling [`Vbri::frames_per_entry`] each time.
+    pub fn toc(&self) -> &[u32] {
+        &self.toc[..self.toc_len]
+    }
+}
+
+/// The decoder delay of the synthesis filterbank, which LAME's encoder delay doesn't include.
+pub(crate) const DECODER_DELAY: u64 = 528 + 1;
+
+/// The LAME extension of a Xing or Info header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LameTag {
+    /// The encoder's name and version, e.g. `b"LAME3.99r"`, padded with spaces or zeros.
+    pub encoder: [u8; 9],
+    /// The revision of the tag format.
+    pub revision: u8,
+    /// The bit rate method: 1 for CBR, 2 for ABR, and 3 to 6 for the VBR methods.
+    pub vbr_method: u8,
+    /// The lowpass filter frequency in Hz, or 0 if unknown. It is stored to the nearest 100 Hz.
+    pub lowpass: u32,
+    /// The peak amplitude of the encoded signal, where 1.0 is full scale, or 0.0 if unknown.
+    pub peak: f32,
+    /// The ReplayGain adjustment for playing tracks in shuffle.
+    pub track_gain: Option<ReplayGain>,
+    /// The ReplayGain adjustment for playing tracks as an album.
+    pub album_gain: Option<ReplayGain>,
+    /// The encoding flags (high nibble) and ATH type (low nibble).
+    pub flags: u8,
+    /// The minimum bit rate of a VBR stream, the average of an ABR stream or the bit rate of a CBR stream,
+    /// in kilobits per second. 255 means 255 or higher.
+    pub bitrate: u8,
+    /// The number of samples of silence added by the encoder at the start of the stream.
+    pub encoder_delay: u16,
+    /// The number of samples of silence added by the encoder to fill the last frame.
+    pub padding: u16,
+    /// The length of the stream in bytes, from the frame holding the tag to the last frame.
+    pub music_length: u32,
+    /// The CRC-16 of the audio frames, which isn't checked.
+    pub music_crc: u16,
+    /// Whether the CRC-16 of the frame up to the end of the tag matched.
+    pub crc_ok: bool
+}
+
+impl LameTag {
+    /// Parses the LAME tag at `offset` in `frame`.
+    fn parse(frame: &[u8], offset: usize) -> Option<Self> {
+        let tag: &[u8; 36] = frame.get(offset..offset + 36)?.try_into().unwrap();
+        // Encoders that don't write the extension leave the rest of the frame zeroed
+        if tag[0] == 0 {
+            return None;
+        }
+        let be_u16 = |i: usize| u16::from_be_bytes([tag[i], tag[i + 1]]);
+        let be_u32 = |i: usize| u32::from_be_bytes(tag[i..i + 4].try_into().unwrap());
+        Some(Self {
+            encoder: tag[..9].try_into().unwrap(),
+            revision: tag[9] >> 4,
+            vbr_method: tag[9] & 0xf,
+            lowpass: u32::from(tag[10]) * 100,
+            peak: be_u32(11) as f32 / (1 << 23) as f32,
+            track_gain: ReplayGain::parse(be_u16(15)),
+            album_gain: ReplayGain::parse(be_u16(17)),
+            flags: tag[19],
+            bitrate: tag[20],
+            encoder_delay: be_u16(21) >> 4,
+            padding: be_u16(22) & 0xfff,
+            music_length: be_u32(28),
+            music_crc: be_u16(32),
+            crc_ok: crc16(&frame[..offset + 34]) == be_u16(34)
+        })
+    }
+}
+
+/// A ReplayGain adjustment stored in a [`LameTag`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayGain {
+    /// Who set the adjustment: 1 for the artist, 2 for the user, 3 for a ReplayGain model, 4 for RMS average.
+    pub originator: u8,
+    /// The adjustment in dB, with a precision of 0.1 dB.
+    pub gain: f32
+}
+
+impl ReplayGain {
+    fn parse(field: u16) -> Option<Self> {
+        // The top 3 bits name the adjustment, with 0 meaning it isn't set
+        if field >> 13 == 0 {
+            return None;
+        }
+        let gain = f32::from(field & 0x1ff) / 10.;
+        Some(Self {
+            originator: (field >> 10 & 7) as u8,
+            gain: if field & 0x200 != 0 { -gain } else { gain }
+        })
+    }
+}
+
+/// The CRC-16 used by LAME tags, with the reflected polynomial 0xA001.
+pub(crate) fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, &b| {
+        (0..8).fold(crc ^ u16::from(b), |crc, _| if crc & 1 != 0 { crc >> 1 ^ 0xa001 } else { crc >> 1 })
+    })
+}